@@ -0,0 +1,393 @@
+// A user-level cache of downloaded artifacts and upstream manifests
+//
+// $XDG_CACHE_HOME/mine/
+//   artifacts/<sha256>         file contents, keyed by their sha256
+//   index/sha1/<sha1>          sha256 of the artifact with that sha1
+//   index/md5/<md5>            sha256 of the artifact with that md5
+//   index/url/<sha256(url)>    sha256 of the artifact last downloaded from url, followed by the url
+//                              (or stored under a key such as buildtools:spigot:1.21:4226 for jars built locally)
+//   manifests/<sha256(url)>    last response body of an upstream API
+//   manifests/<sha256(url)>.json  its url, ETag, Last-Modified and fetch time
+
+use reqwest::header::{HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config;
+use crate::downloader::Checksum;
+use crate::error::{MineError, Result};
+use crate::warning;
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+pub fn cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("mine"))
+}
+
+fn url_key(url: &str) -> String {
+    format!("{:x}", Sha256::digest(url.as_bytes()))
+}
+
+// how long a cached manifest is used without asking upstream whether it changed
+const MANIFEST_TTL: Duration = Duration::from_secs(10 * 60);
+
+// manifests fetched during this run, so every provider shares a single request per url
+static FETCHED: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+// GitHub's API rejects requests without a user agent
+const USER_AGENT: &str = concat!("mine/", env!("CARGO_PKG_VERSION"));
+
+// a server that stops answering, or stops sending halfway through a body without closing the connection,
+// fails the request instead of hanging; downloads retry it like any other interrupted transfer
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+// the client for every request to upstream, shared so connections are reused
+pub fn client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .read_timeout(READ_TIMEOUT)
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+// stored next to a cached manifest as "<key>.json"
+#[derive(Serialize, Deserialize)]
+struct ManifestMeta {
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>,
+    // unix timestamp of the last time upstream confirmed this body
+    fetched_at: u64,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+// GETs an upstream API. Responses are shared within a run and cached on disk, where they are reused
+// for MANIFEST_TTL and then revalidated with If-None-Match/If-Modified-Since. With --offline only the cache is used.
+pub async fn fetch(url: &str) -> Result<String> {
+    let fetched = FETCHED.get_or_init(Default::default);
+    if let Some(body) = fetched.lock().unwrap().get(url) {
+        return Ok(body.clone());
+    }
+
+    let body = fetch_uncached(url).await?;
+    fetched.lock().unwrap().insert(url.to_string(), body.clone());
+    Ok(body)
+}
+
+async fn fetch_uncached(url: &str) -> Result<String> {
+    let path = cache_dir().map(|dir| dir.join("manifests").join(url_key(url)));
+    let meta_path = path.as_ref().map(|path| path.with_extension("json"));
+
+    let cached = match (&path, &meta_path) {
+        (Some(path), Some(meta_path)) => fs::read_to_string(path).ok().zip(
+            fs::read_to_string(meta_path).ok().and_then(|meta| serde_json::from_str::<ManifestMeta>(&meta).ok()),
+        ),
+        _ => None,
+    };
+
+    if is_offline() {
+        return match cached {
+            Some((body, _)) => Ok(body),
+            None => Err(MineError::NotCached(url.to_string())),
+        };
+    }
+
+    if let Some((body, meta)) = &cached {
+        if unix_now().saturating_sub(meta.fetched_at) < MANIFEST_TTL.as_secs() {
+            return Ok(body.clone());
+        }
+    }
+
+    // the configured URL for this upstream, then its mirrors until one answers
+    let mut candidates = config::urls(url).into_iter().peekable();
+    let response = loop {
+        let Some(candidate) = candidates.next() else {
+            return Err(MineError::Other(format!("No URL to fetch {} from", url)));
+        };
+        match get(&candidate, cached.as_ref().map(|(_, meta)| meta)).await {
+            Ok(response) => break response,
+            Err(e) => match candidates.peek() {
+                Some(next) => warning::warn(&format!("{}, trying {}", e, next)),
+                None => return Err(e),
+            },
+        }
+    };
+    let status = response.status();
+    let header = |name| response.headers().get(name).and_then(|v: &HeaderValue| v.to_str().ok()).map(str::to_string);
+
+    let (body, meta) = match cached {
+        Some((body, mut meta)) if status == StatusCode::NOT_MODIFIED => {
+            meta.fetched_at = unix_now();
+            (body, meta)
+        },
+        _ => {
+            let meta = ManifestMeta {
+                url: url.to_string(),
+                etag: header(ETAG),
+                last_modified: header(LAST_MODIFIED),
+                fetched_at: unix_now(),
+            };
+            (response.text().await?, meta)
+        },
+    };
+
+    if let (Some(path), Some(meta_path)) = (path, meta_path) {
+        let written = write_atomically(&path, body.as_bytes())
+            .and_then(|_| write_atomically(&meta_path, serde_json::to_string(&meta)?.as_bytes()));
+        if let Err(e) = written {
+            warning::warn(&format!("Failed to cache {}: {}", url, e));
+        }
+    }
+
+    Ok(body)
+}
+
+// a GET, conditional on the cached copy; errors on any status but success or, with a cached copy, Not Modified
+async fn get(url: &str, cached: Option<&ManifestMeta>) -> Result<reqwest::Response> {
+    let mut request = client().get(url);
+    if let Some(meta) = cached {
+        if let Some(etag) = &meta.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &meta.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+    let not_modified = status == StatusCode::NOT_MODIFIED && cached.is_some();
+    if !status.is_success() && !not_modified {
+        return Err(MineError::HttpStatus { url: url.to_string(), status: status.as_u16() });
+    }
+    Ok(response)
+}
+
+pub async fn fetch_json<T: DeserializeOwned>(url: &str) -> Result<T> {
+    let body = fetch(url).await?;
+    serde_json::from_str(&body).map_err(|e| MineError::parse(url, e))
+}
+
+pub fn is_hex(value: &str, len: usize) -> bool {
+    value.len() == len && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+// where an artifact is stored, if sha256 really is one and cannot point outside the cache
+fn artifact_path(dir: &Path, sha256: &str) -> Option<PathBuf> {
+    is_hex(sha256, 64).then(|| dir.join("artifacts").join(sha256.to_lowercase()))
+}
+
+// the cached file for an artifact, looked up by its expected checksum or, failing that, by its url
+pub fn lookup(url: &str, checksum: Option<&Checksum>) -> Option<PathBuf> {
+    let dir = cache_dir()?;
+    let sha256 = match checksum {
+        Some(checksum) if !checksum.is_well_formed() => return None,
+        Some(Checksum::Sha256(sha256)) => sha256.clone(),
+        Some(checksum) => read_index(&dir.join("index").join(checksum.algorithm()).join(checksum.value().to_lowercase()))?,
+        None => read_index(&dir.join("index").join("url").join(url_key(url)))?,
+    };
+
+    let path = artifact_path(&dir, &sha256)?;
+    if !path.is_file() {
+        return None;
+    }
+
+    // the modification time doubles as the last use for `mine cache prune`
+    if let Ok(file) = fs::File::options().append(true).open(&path) {
+        file.set_modified(SystemTime::now()).ok();
+    }
+    Some(path)
+}
+
+fn read_index(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    content.lines().next().map(|line| line.trim().to_string())
+}
+
+// removes a broken artifact returned by lookup, and nothing outside the cache's artifacts
+pub fn evict(path: &Path) {
+    let Some(dir) = cache_dir() else {
+        return;
+    };
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    if artifact_path(&dir, name).is_some_and(|artifact| artifact == path) {
+        fs::remove_file(path).ok();
+    }
+}
+
+// copies a verified download into the cache
+pub fn store(file: &Path, url: &str, sha256: &str, checksum: Option<&Checksum>) -> io::Result<()> {
+    let Some(dir) = cache_dir() else {
+        return Ok(());
+    };
+
+    let Some(artifact) = artifact_path(&dir, sha256) else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} is not a sha256", sha256)));
+    };
+    let sha256 = sha256.to_lowercase();
+    if !artifact.is_file() {
+        fs::create_dir_all(dir.join("artifacts"))?;
+        let tmp = artifact.with_extension("tmp");
+        fs::copy(file, &tmp)?;
+        fs::rename(&tmp, &artifact)?;
+    }
+
+    if let Some(checksum @ (Checksum::Sha1(_) | Checksum::Md5(_))) = checksum.filter(|checksum| checksum.is_well_formed()) {
+        write_atomically(&dir.join("index").join(checksum.algorithm()).join(checksum.value().to_lowercase()), sha256.as_bytes())?;
+    }
+    write_atomically(&dir.join("index").join("url").join(url_key(url)), format!("{}\n{}\n", sha256, url).as_bytes())
+}
+
+// caches a file that was not downloaded, e.g. a jar built locally, under a key looked up like a url
+pub fn store_file(file: &Path, key: &str) -> io::Result<()> {
+    let sha256 = format!("{:x}", Sha256::digest(fs::read(file)?));
+    store(file, key, &sha256, None)
+}
+
+fn write_atomically(path: &Path, content: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path)
+}
+
+pub struct Entry {
+    pub sha256: String,
+    pub size: u64,
+    pub last_used: SystemTime,
+    pub urls: Vec<String>,
+}
+
+pub fn entries() -> io::Result<Vec<Entry>> {
+    let Some(dir) = cache_dir() else {
+        return Ok(Vec::new());
+    };
+
+    let mut urls: Vec<(String, String)> = Vec::new();
+    for (_, path) in read_dir(&dir.join("index").join("url"))? {
+        if let Ok(content) = fs::read_to_string(&path) {
+            let mut lines = content.lines();
+            if let (Some(sha256), Some(url)) = (lines.next(), lines.next()) {
+                urls.push((sha256.to_string(), url.to_string()));
+            }
+        }
+    }
+
+    let mut entries = Vec::new();
+    // anything else in there was not put there by mine
+    for (sha256, path) in read_dir(&dir.join("artifacts"))?.into_iter().filter(|(name, _)| is_hex(name, 64)) {
+        let metadata = fs::metadata(&path)?;
+        entries.push(Entry {
+            urls: urls.iter().filter(|(hash, _)| *hash == sha256).map(|(_, url)| url.clone()).collect(),
+            sha256,
+            size: metadata.len(),
+            last_used: metadata.modified()?,
+        });
+    }
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.last_used));
+    Ok(entries)
+}
+
+fn read_dir(dir: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file() && entry.path().extension().is_none())
+            .map(|entry| Ok((entry.file_name().to_string_lossy().to_string(), entry.path())))
+            .collect(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+// removes artifacts not used within max_age and index entries pointing at removed artifacts;
+// returns the number of artifacts and bytes freed
+pub fn prune(max_age: Duration) -> io::Result<(usize, u64)> {
+    let Some(dir) = cache_dir() else {
+        return Ok((0, 0));
+    };
+
+    let now = SystemTime::now();
+    let mut removed = 0;
+    let mut freed = 0;
+    for entry in entries()? {
+        if now.duration_since(entry.last_used).unwrap_or_default() > max_age {
+            fs::remove_file(dir.join("artifacts").join(&entry.sha256))?;
+            removed += 1;
+            freed += entry.size;
+        }
+    }
+
+    for index in ["sha1", "md5", "url"] {
+        for (_, path) in read_dir(&dir.join("index").join(index))? {
+            let target = read_index(&path).and_then(|sha256| artifact_path(&dir, &sha256));
+            if !target.is_some_and(|target| target.is_file()) {
+                fs::remove_file(&path)?;
+            }
+        }
+    }
+
+    Ok((removed, freed))
+}
+
+pub fn clear() -> io::Result<()> {
+    match cache_dir() {
+        Some(dir) if dir.exists() => fs::remove_dir_all(dir),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn artifact_path_rejects_anything_but_a_sha256() {
+        let dir = Path::new("/cache");
+        let sha256 = "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855";
+        assert_eq!(artifact_path(dir, sha256), Some(dir.join("artifacts").join(sha256.to_lowercase())));
+        assert_eq!(artifact_path(dir, "../../../victim.txt"), None);
+        assert_eq!(artifact_path(dir, &"a".repeat(63)), None);
+        assert_eq!(artifact_path(dir, &format!("{}/", &sha256[..63])), None);
+    }
+
+    #[test]
+    fn checksums_must_be_hex_digests() {
+        assert!(Checksum::Md5("d41d8cd98f00b204e9800998ecf8427e".to_string()).is_well_formed());
+        assert!(!Checksum::Md5("da39a3ee5e6b4b0d3255bfef95601890afd80709".to_string()).is_well_formed());
+        assert!(!Checksum::Md5("../../index/url/d41d8cd98f00b204".to_string()).is_well_formed());
+        assert!(lookup("https://example.com", Some(&Checksum::Md5("../../../victim.txt".to_string()))).is_none());
+        assert!(!Checksum::Sha1("../../index/url/da39a3ee5e6b4b0d3255bfef".to_string()).is_well_formed());
+        assert!(!Checksum::Sha256("../../../victim.txt".to_string()).is_well_formed());
+        assert!(lookup("https://example.com", Some(&Checksum::Sha256("../../../victim.txt".to_string()))).is_none());
+    }
+}
@@ -0,0 +1,204 @@
+// The user config file, which points upstreams at other URLs and mirrors
+//
+// $XDG_CONFIG_HOME/mine/config.toml, or the file in $MINE_CONFIG:
+//
+//   [upstreams.fabric]
+//   url = "http://localhost:8080"                    # used instead of https://meta.fabricmc.net
+//   mirrors = ["https://mirror.example.com/fabric"]  # tried in order when the url fails
+//
+// MINE_UPSTREAM_<NAME>_URL and MINE_UPSTREAM_<NAME>_MIRRORS (comma separated) override the file,
+// e.g. MINE_UPSTREAM_LEGACY_FABRIC_URL. Requests are rewritten where they are sent, so mine.lock
+// and the cache keep the upstream URLs and stay valid whichever mirror served them.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::error::{MineError, Result};
+
+// every upstream by its name in the config file, with the base URL its requests start with
+pub const UPSTREAMS: &[(&str, &str)] = &[
+    ("mojang-meta", "https://piston-meta.mojang.com"),
+    ("mojang-data", "https://piston-data.mojang.com"),
+    ("papermc", "https://api.papermc.io"),
+    ("purpur", "https://api.purpurmc.org"),
+    ("fabric", "https://meta.fabricmc.net"),
+    ("legacy-fabric", "https://meta.legacyfabric.net"),
+    ("babric", "https://meta.babric.glass-launcher.net"),
+    ("quilt-meta", "https://meta.quiltmc.org"),
+    ("quilt-maven", "https://maven.quiltmc.org"),
+    ("forge-files", "https://files.minecraftforge.net"),
+    ("forge-maven", "https://maven.minecraftforge.net"),
+    ("neoforge-maven", "https://maven.neoforged.net"),
+    ("spigot", "https://hub.spigotmc.org"),
+    ("bungeecord", "https://ci.md-5.net"),
+    ("sponge-api", "https://dl-api.spongepowered.org"),
+    ("sponge-repo", "https://repo.spongepowered.org"),
+    ("mohist", "https://mohistmc.com"),
+    ("arclight-api", "https://api.github.com/repos/IzzelAliz/Arclight"),
+    ("arclight", "https://github.com/IzzelAliz/Arclight"),
+    ("bedrock-links", "https://net-secondary.web.minecraft-services.net"),
+    ("bedrock", "https://www.minecraft.net/bedrockdedicatedserver"),
+    ("modrinth", "https://api.modrinth.com/v2"),
+    ("modrinth-cdn", "https://cdn.modrinth.com"),
+];
+
+pub const CONFIG_ENV: &str = "MINE_CONFIG";
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    upstreams: HashMap<String, UpstreamConfig>,
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+struct UpstreamConfig {
+    url: Option<String>,
+    #[serde(default)]
+    mirrors: Vec<String>,
+}
+
+// the URLs tried for an upstream's requests, in order
+struct Upstream {
+    base: &'static str,
+    urls: Vec<String>,
+}
+
+static UPSTREAM_URLS: OnceLock<Vec<Upstream>> = OnceLock::new();
+
+pub fn config_path() -> Option<PathBuf> {
+    match env::var_os(CONFIG_ENV) {
+        Some(path) => Some(PathBuf::from(path)),
+        None => dirs::config_dir().map(|dir| dir.join("mine").join("config.toml")),
+    }
+}
+
+fn trim(url: &str) -> String {
+    url.trim().trim_end_matches('/').to_string()
+}
+
+// reads the config file and environment; without it every request goes to its upstream
+pub fn init() -> Result<()> {
+    let mut file = ConfigFile::default();
+    if let Some(path) = config_path() {
+        match fs::read_to_string(&path) {
+            Ok(content) => file = toml::from_str(&content).map_err(|e| MineError::parse(path.display().to_string(), e))?,
+            // only a missing default file is fine, a missing $MINE_CONFIG is a mistake
+            Err(e) if e.kind() == io::ErrorKind::NotFound && env::var_os(CONFIG_ENV).is_none() => {},
+            Err(e) => return Err(MineError::Other(format!("Failed to read {}: {}", path.display(), e))),
+        }
+    }
+
+    if let Some(name) = file.upstreams.keys().find(|name| !UPSTREAMS.iter().any(|(upstream, _)| upstream == name)) {
+        let names: Vec<&str> = UPSTREAMS.iter().map(|(name, _)| *name).collect();
+        return Err(MineError::Other(format!("Unknown upstream {} in {}, expected one of {}", name, config_path().unwrap_or_default().display(), names.join(", "))));
+    }
+
+    // a second call keeps the first configuration
+    UPSTREAM_URLS.set(upstreams(&file, |var| env::var(var).ok())).ok();
+    Ok(())
+}
+
+// the URLs of every upstream from the config file, overridden by the environment variables var returns
+fn upstreams(file: &ConfigFile, var: impl Fn(&str) -> Option<String>) -> Vec<Upstream> {
+    UPSTREAMS.iter()
+        .map(|(name, base)| {
+            let mut config = file.upstreams.get(*name).cloned().unwrap_or_default();
+            let prefix = format!("MINE_UPSTREAM_{}", name.to_uppercase().replace('-', "_"));
+            if let Some(url) = var(&format!("{}_URL", prefix)) {
+                config.url = Some(url);
+            }
+            if let Some(mirrors) = var(&format!("{}_MIRRORS", prefix)) {
+                config.mirrors = mirrors.split(',').filter(|mirror| !mirror.trim().is_empty()).map(str::to_string).collect();
+            }
+
+            let mut urls = vec![trim(config.url.as_deref().unwrap_or(base))];
+            urls.extend(config.mirrors.iter().map(|mirror| trim(mirror)));
+            Upstream { base, urls }
+        })
+        .collect()
+}
+
+// the URLs to try for a request, the configured one first and then its mirrors;
+// just the URL itself when it is not from a known upstream or nothing is configured
+pub fn urls(url: &str) -> Vec<String> {
+    rewrite(UPSTREAM_URLS.get().map_or(&[], Vec::as_slice), url)
+}
+
+fn rewrite(upstreams: &[Upstream], url: &str) -> Vec<String> {
+    let upstream = upstreams.iter()
+        .filter(|upstream| url.strip_prefix(upstream.base).is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '?'])))
+        .max_by_key(|upstream| upstream.base.len());
+
+    match upstream {
+        Some(upstream) => upstream.urls.iter().map(|base| format!("{}{}", base, &url[upstream.base.len()..])).collect(),
+        None => vec![url.to_string()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configured(file: &str, vars: &[(&str, &str)]) -> Vec<Upstream> {
+        let file: ConfigFile = toml::from_str(file).unwrap();
+        upstreams(&file, |var| vars.iter().find(|(name, _)| *name == var).map(|(_, value)| value.to_string()))
+    }
+
+    #[test]
+    fn urls_are_unchanged_by_default() {
+        let upstreams = configured("", &[]);
+        let url = "https://meta.fabricmc.net/v2/versions";
+        assert_eq!(rewrite(&upstreams, url), [url]);
+        assert_eq!(rewrite(&[], url), [url]);
+        assert_eq!(rewrite(&upstreams, "https://example.com/file.jar"), ["https://example.com/file.jar"]);
+    }
+
+    #[test]
+    fn urls_use_the_configured_url_then_mirrors() {
+        let upstreams = configured(r#"
+            [upstreams.fabric]
+            url = "http://localhost:8080/"
+            mirrors = ["https://mirror.example.com/fabric"]
+        "#, &[]);
+        assert_eq!(rewrite(&upstreams, "https://meta.fabricmc.net/v2/versions?a=1"), [
+            "http://localhost:8080/v2/versions?a=1",
+            "https://mirror.example.com/fabric/v2/versions?a=1",
+        ]);
+        assert_eq!(rewrite(&upstreams, "https://meta.fabricmc.net"), ["http://localhost:8080", "https://mirror.example.com/fabric"]);
+        // only whole hosts and path segments match
+        assert_eq!(rewrite(&upstreams, "https://meta.fabricmc.net.example.com/v2"), ["https://meta.fabricmc.net.example.com/v2"]);
+    }
+
+    #[test]
+    fn the_environment_overrides_the_file() {
+        let upstreams = configured(r#"
+            [upstreams.legacy-fabric]
+            url = "http://file.example.com"
+            mirrors = ["http://file-mirror.example.com"]
+        "#, &[("MINE_UPSTREAM_LEGACY_FABRIC_URL", "http://env.example.com"), ("MINE_UPSTREAM_LEGACY_FABRIC_MIRRORS", "http://a.example.com, ,http://b.example.com/")]);
+        assert_eq!(rewrite(&upstreams, "https://meta.legacyfabric.net/v2/versions"), [
+            "http://env.example.com/v2/versions",
+            "http://a.example.com/v2/versions",
+            "http://b.example.com/v2/versions",
+        ]);
+    }
+
+    #[test]
+    fn the_longest_base_wins() {
+        let upstreams = configured(r#"
+            [upstreams.arclight]
+            url = "http://releases.example.com"
+            [upstreams.arclight-api]
+            url = "http://api.example.com"
+        "#, &[]);
+        assert_eq!(rewrite(&upstreams, "https://github.com/IzzelAliz/Arclight/releases/download/1.0.5/a.jar"), ["http://releases.example.com/releases/download/1.0.5/a.jar"]);
+        assert_eq!(rewrite(&upstreams, "https://api.github.com/repos/IzzelAliz/Arclight/releases?page=1"), ["http://api.example.com/releases?page=1"]);
+    }
+}
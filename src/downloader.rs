@@ -1,39 +1,420 @@
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use tokio::sync::mpsc;
 use std::error::Error;
-use std::path::Path;
-use reqwest::Client;
+use std::fmt;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::{Client, StatusCode};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
 
+use crate::cache;
+use crate::config;
+use crate::error::{MineError, Result};
+use crate::warning;
+
+// a digest published by upstream, as lowercase hex
+#[derive(Debug, Clone, PartialEq)]
+pub enum Checksum {
+    Md5(String),
+    Sha1(String),
+    Sha256(String),
+}
+
+impl Checksum {
+    pub fn algorithm(&self) -> &'static str {
+        match self {
+            Checksum::Md5(_) => "md5",
+            Checksum::Sha1(_) => "sha1",
+            Checksum::Sha256(_) => "sha256",
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        match self {
+            Checksum::Md5(value) | Checksum::Sha1(value) | Checksum::Sha256(value) => value,
+        }
+    }
+
+    // hex of the digest's length; checksums come from lockfiles and upstream JSON and name files in the cache
+    pub fn is_well_formed(&self) -> bool {
+        let len = match self {
+            Checksum::Md5(_) => 32,
+            Checksum::Sha1(_) => 40,
+            Checksum::Sha256(_) => 64,
+        };
+        cache::is_hex(self.value(), len)
+    }
+}
+
+#[derive(Debug)]
+pub struct ChecksumError {
+    pub algorithm: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for ChecksumError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} checksum mismatch: expected {}, got {}", self.algorithm, self.expected, self.actual)
+    }
+}
+
+impl Error for ChecksumError {}
+
+pub struct DownloadedFile {
+    pub size: u64,
+    pub sha256: String,
+}
+
+const MAX_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+// whether a failed attempt is worth retrying
+enum AttemptError {
+    Transient(MineError),
+    Fatal(MineError),
+}
+
+impl From<reqwest::Error> for AttemptError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() || e.is_connect() || e.is_request() || e.is_body() || e.is_decode() {
+            AttemptError::Transient(MineError::Network(e))
+        } else {
+            AttemptError::Fatal(e.into())
+        }
+    }
+}
+
+impl From<std::io::Error> for AttemptError {
+    fn from(e: std::io::Error) -> Self {
+        AttemptError::Fatal(MineError::Io(e))
+    }
+}
+
+struct Hashers {
+    sha256: Sha256,
+    sha1: Option<Sha1>,
+    md5: Option<Md5>,
+}
+
+impl Hashers {
+    fn new(checksum: Option<&Checksum>) -> Self {
+        Hashers {
+            sha256: Sha256::new(),
+            sha1: matches!(checksum, Some(Checksum::Sha1(_))).then(Sha1::new),
+            md5: matches!(checksum, Some(Checksum::Md5(_))).then(Md5::new),
+        }
+    }
+
+    fn update(&mut self, chunk: &[u8]) {
+        self.sha256.update(chunk);
+        if let Some(sha1) = &mut self.sha1 {
+            sha1.update(chunk);
+        }
+        if let Some(md5) = &mut self.md5 {
+            md5.update(chunk);
+        }
+    }
+}
+
+// state carried across attempts of a single download
+struct Transfer<'a> {
+    file: tokio::fs::File,
+    downloaded: u64,
+    hashers: Hashers,
+    checksum: Option<&'a Checksum>,
+    length_sent: bool,
+    progress_tx: mpsc::Sender<u64>,
+    length_tx: mpsc::Sender<Option<u64>>,
+}
+
+impl Transfer<'_> {
+    async fn attempt(&mut self, client: &Client, url: &str) -> std::result::Result<(), AttemptError> {
+        let mut request = client.get(url);
+        if self.downloaded > 0 {
+            request = request.header(RANGE, format!("bytes={}-", self.downloaded));
+        }
+
+        let mut response = request.send().await?;
+        let status = response.status();
+
+        // a part from an earlier run that already has every byte
+        if status == StatusCode::RANGE_NOT_SATISFIABLE && self.downloaded > 0 {
+            return Ok(());
+        }
+
+        if !status.is_success() {
+            let error = MineError::HttpStatus { url: url.to_string(), status: status.as_u16() };
+            let transient = status.is_server_error()
+                || status == StatusCode::TOO_MANY_REQUESTS
+                || status == StatusCode::REQUEST_TIMEOUT;
+            return Err(if transient { AttemptError::Transient(error) } else { AttemptError::Fatal(error) });
+        }
+
+        // the server ignored the range request, so start over
+        if self.downloaded > 0 && status != StatusCode::PARTIAL_CONTENT {
+            self.restart().await?;
+        }
+
+        // a part that does not continue the file, e.g. from a proxy, is thrown away with what came before
+        if status == StatusCode::PARTIAL_CONTENT {
+            let start = response.headers().get(CONTENT_RANGE).and_then(|range| range.to_str().ok()).and_then(content_range_start);
+            let expected = self.downloaded;
+            if start != Some(expected) {
+                self.restart().await?;
+                return Err(AttemptError::Transient(MineError::Other(format!("{} did not continue at byte {}", url, expected))));
+            }
+        }
+
+        let total = response.content_length().map(|len| len + self.downloaded);
+        self.send_length(total).await.map_err(AttemptError::Fatal)?;
+
+        while let Some(chunk) = response.chunk().await? {
+            self.write(&chunk).await?;
+        }
+
+        Ok(())
+    }
+
+    // attempts a download until it completes, backing off between transient errors
+    async fn download(&mut self, client: &Client, url: &str) -> Result<()> {
+        let mut attempt = 1;
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            match self.attempt(client, url).await {
+                Ok(()) => return Ok(()),
+                Err(AttemptError::Transient(e)) if attempt < MAX_ATTEMPTS => {
+                    warning::warn(&format!("Download interrupted ({}), retrying in {:.1}s...", e, backoff.as_secs_f64()));
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                    backoff *= 2;
+                },
+                Err(AttemptError::Transient(e)) | Err(AttemptError::Fatal(e)) => return Err(e),
+            }
+        }
+    }
+
+    // downloads url and checks it, returning its sha256
+    async fn fetch(&mut self, client: &Client, url: &str) -> Result<String> {
+        self.download(client, url).await?;
+        self.verify().await
+    }
+
+    // continues after what the file already holds
+    async fn resume(&mut self) -> std::io::Result<()> {
+        self.file.seek(SeekFrom::Start(0)).await?;
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+            let read = self.file.read(&mut buffer).await?;
+            if read == 0 {
+                return Ok(());
+            }
+            self.hashers.update(&buffer[..read]);
+            self.downloaded += read as u64;
+        }
+    }
+
+    async fn copy_from(&mut self, cached: &Path) -> Result<()> {
+        let mut source = tokio::fs::File::open(cached).await?;
+        self.send_length(Some(source.metadata().await?.len())).await?;
+
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+            let read = source.read(&mut buffer).await?;
+            if read == 0 {
+                return Ok(());
+            }
+            self.write(&buffer[..read]).await?;
+        }
+    }
+
+    async fn restart(&mut self) -> std::io::Result<()> {
+        self.file.set_len(0).await?;
+        self.file.seek(SeekFrom::Start(0)).await?;
+        self.downloaded = 0;
+        self.hashers = Hashers::new(self.checksum);
+        Ok(())
+    }
+
+    // only the first length is reported, later ones would be for resumed or restarted transfers
+    async fn send_length(&mut self, length: Option<u64>) -> Result<()> {
+        if !self.length_sent {
+            self.length_sent = true;
+            self.length_tx.send(length).await.map_err(|e| MineError::Other(format!("Failed to send download length: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    async fn write(&mut self, chunk: &[u8]) -> std::io::Result<()> {
+        self.file.write_all(chunk).await?;
+        self.hashers.update(chunk);
+        self.downloaded += chunk.len() as u64;
+        // fails only once nobody is showing the progress any more
+        self.progress_tx.send(self.downloaded).await.ok();
+        Ok(())
+    }
+
+    // flushes the file and checks it against the expected checksum, returning its sha256
+    async fn verify(&mut self) -> Result<String> {
+        self.file.flush().await?;
+        self.file.sync_all().await?;
+
+        let sha256 = format!("{:x}", self.hashers.sha256.clone().finalize());
+        if let Some(checksum) = self.checksum {
+            let actual = match (checksum, &self.hashers.sha1, &self.hashers.md5) {
+                (Checksum::Sha1(_), Some(sha1), _) => format!("{:x}", sha1.clone().finalize()),
+                (Checksum::Md5(_), _, Some(md5)) => format!("{:x}", md5.clone().finalize()),
+                _ => sha256.clone(),
+            };
+
+            if !actual.eq_ignore_ascii_case(checksum.value()) {
+                return Err(MineError::Checksum(ChecksumError {
+                    algorithm: checksum.algorithm(),
+                    expected: checksum.value().to_string(),
+                    actual,
+                }));
+            }
+        }
+
+        Ok(sha256)
+    }
+}
+
+// the first byte of a "Content-Range: bytes 100-199/1000" response
+fn content_range_start(range: &str) -> Option<u64> {
+    range.strip_prefix("bytes ")?.split_once('-')?.0.trim().parse().ok()
+}
+
+// the download goes to "<name>.part" next to the target and is renamed into place only once it is complete and verified;
+// when the expected checksum is known, a .part left by an interrupted run is kept and continued by the next one
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+// streams url into path, hashing on the way, retrying transient errors and resuming with HTTP Range where possible,
+// also across runs when the checksum is known (see part_path);
+// artifacts already in the local cache are copied from there instead, and configured mirrors are tried when url fails.
+// path is left untouched unless the download completes and matches the expected checksum
 pub async fn download_file(
     url: &str,
     path: &Path,
+    checksum: Option<&Checksum>,
     progress_tx: mpsc::Sender<u64>,
     length_tx: mpsc::Sender<Option<u64>>,
-) -> Result<(), Box<dyn Error>> {
-    let client = Client::new();
-    let mut response = client.get(url).send().await?;
+) -> Result<DownloadedFile> {
+    if let Some(checksum) = checksum.filter(|checksum| !checksum.is_well_formed()) {
+        return Err(MineError::parse(format!("{} checksum of {}", checksum.algorithm(), url), format!("\"{}\" is not a {} digest", checksum.value(), checksum.algorithm())));
+    }
+    let part_path = part_path(path);
+
+    let mut transfer = Transfer {
+        file: tokio::fs::File::options().read(true).write(true).create(true).truncate(false).open(&part_path).await?,
+        downloaded: 0,
+        hashers: Hashers::new(checksum),
+        checksum,
+        length_sent: false,
+        progress_tx,
+        length_tx,
+    };
+
+    if let Some(cached) = cache::lookup(url, checksum) {
+        transfer.restart().await?;
+        let copied = match transfer.copy_from(&cached).await {
+            Ok(()) => transfer.verify().await,
+            Err(e) => Err(e),
+        };
 
-    if !response.status().is_success() {
-        return Err(format!("Failed to download file: HTTP {}", response.status()).into());
+        match copied {
+            Ok(sha256) => {
+                let downloaded = transfer.downloaded;
+                drop(transfer);
+                tokio::fs::rename(&part_path, path).await?;
+                return Ok(DownloadedFile { size: downloaded, sha256 });
+            },
+            Err(e) => {
+                warning::warn(&format!("Ignoring broken cache entry {}: {}", cached.display(), e));
+                cache::evict(&cached);
+                transfer.restart().await?;
+            },
+        }
     }
 
-    let content_length = response.content_length();
-    if let Some(len) = content_length {
-        length_tx.send(Some(len)).await?;
-    } else {
-        length_tx.send(None).await?;
+    if cache::is_offline() {
+        let empty = transfer.file.metadata().await.is_ok_and(|metadata| metadata.len() == 0);
+        drop(transfer);
+        if empty {
+            tokio::fs::remove_file(&part_path).await.ok();
+        }
+        return Err(MineError::NotCached(url.to_string()));
     }
 
-    let mut downloaded: u64 = 0;
+    // without a checksum there is no telling whether an old .part belongs to this file
+    match checksum {
+        Some(_) => transfer.resume().await?,
+        None => transfer.restart().await?,
+    }
+    let mut resumed = transfer.downloaded > 0;
 
-    let mut file = tokio::fs::File::create(path).await?;
-    while let Some(chunk) = response.chunk().await? {
-        file.write_all(&chunk).await?;
-        downloaded += chunk.len() as u64;
-        if let Err(e) = progress_tx.send(downloaded).await {
-            eprintln!("Failed to send progress update: {}", e);
+    // the configured URL for this upstream, then its mirrors, each starting over
+    let client = cache::client();
+    let mut candidates = config::urls(url).into_iter().peekable();
+    let verified = loop {
+        let Some(candidate) = candidates.next() else {
+            break Err(MineError::Other(format!("No URL to download {} from", url)));
+        };
+        let mut verified = transfer.fetch(client, &candidate).await;
+        // the .part was from another version of the file
+        if let (true, Err(MineError::Checksum(_))) = (resumed, &verified) {
+            transfer.restart().await?;
+            verified = transfer.fetch(client, &candidate).await;
         }
+        resumed = false;
+        match (verified, candidates.peek()) {
+            (Err(e), Some(next)) => {
+                warning::warn(&format!("{}, trying {}", e, next));
+                transfer.restart().await?;
+            },
+            (verified, _) => break verified,
+        }
+    };
+    let downloaded = transfer.downloaded;
+    drop(transfer);
+
+    let sha256 = match verified {
+        Ok(sha256) => sha256,
+        Err(e) => {
+            if checksum.is_none() || matches!(e, MineError::Checksum(_)) {
+                tokio::fs::remove_file(&part_path).await.ok();
+            }
+            return Err(e);
+        },
+    };
+
+    if let Err(e) = cache::store(&part_path, url, &sha256, checksum) {
+        warning::warn(&format!("Failed to cache {}: {}", url, e));
     }
 
-    Ok(())
+    tokio::fs::rename(&part_path, path).await?;
+
+    Ok(DownloadedFile {
+        size: downloaded,
+        sha256,
+    })
+}
+
+// download_file without a progress bar, for files a server's installer needs
+pub async fn download_quietly(url: &str, path: &Path, checksum: Option<&Checksum>) -> Result<DownloadedFile> {
+    let (progress_tx, mut progress_rx) = mpsc::channel(100);
+    let (length_tx, _length_rx) = mpsc::channel(1);
+    let (downloaded, ()) = tokio::join!(
+        download_file(url, path, checksum, progress_tx, length_tx),
+        async { while progress_rx.recv().await.is_some() {} },
+    );
+    downloaded
 }
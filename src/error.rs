@@ -0,0 +1,125 @@
+use std::fmt;
+
+use crate::downloader::ChecksumError;
+
+pub type Result<T> = std::result::Result<T, MineError>;
+
+#[derive(Debug)]
+pub enum MineError {
+    // the request never got a response
+    Network(reqwest::Error),
+    // the response had an unsuccessful status
+    HttpStatus { url: String, status: u16 },
+    // suggestions are the closest valid values, shown instead of the latest one when there are any
+    // what is e.g. "Minecraft version" or "BungeeCord build"
+    VersionNotFound { what: &'static str, version: String, latest: Option<String>, suggestions: Vec<String> },
+    BuildNotFound { version: String, build: String, latest: Option<String>, suggestions: Vec<String> },
+    LoaderNotFound { loader: String, latest: Option<String>, suggestions: Vec<String> },
+    InstallerNotFound { installer: String, latest: Option<String>, suggestions: Vec<String> },
+    PluginNotFound { name: String, reason: String },
+    // a manifest, lockfile or upstream response could not be parsed (or written)
+    Parse { what: String, message: String },
+    Checksum(ChecksumError),
+    Io(std::io::Error),
+    // --offline was given but something is missing from the cache
+    NotCached(String),
+    Other(String),
+}
+
+impl MineError {
+    pub fn parse(what: impl Into<String>, error: impl fmt::Display) -> Self {
+        MineError::Parse { what: what.into(), message: error.to_string() }
+    }
+
+    // the process exit code for this error, so scripts can tell failures apart
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            MineError::Other(_) => 1,
+            // 2 is used by clap for usage errors
+            MineError::Network(_) => 3,
+            MineError::HttpStatus { .. } => 4,
+            MineError::VersionNotFound { .. } => 5,
+            MineError::BuildNotFound { .. } => 6,
+            MineError::LoaderNotFound { .. } => 7,
+            MineError::InstallerNotFound { .. } => 8,
+            MineError::PluginNotFound { .. } => 9,
+            MineError::Parse { .. } => 10,
+            MineError::Checksum(_) => 11,
+            MineError::Io(_) => 12,
+            MineError::NotCached(_) => 13,
+        }
+    }
+}
+
+// e.g. ". Did you mean 1.20.6 or 1.21?" or ". Latest is 1.21"
+fn hint(latest: &Option<String>, suggestions: &[String]) -> String {
+    match (suggestions, latest) {
+        ([], Some(latest)) => format!(". Latest is {}", latest),
+        ([], None) => String::new(),
+        ([suggestion], _) => format!(". Did you mean {}?", suggestion),
+        ([rest @ .., last], _) => format!(". Did you mean {} or {}?", rest.join(", "), last),
+    }
+}
+
+impl fmt::Display for MineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MineError::Network(e) => write!(f, "Network error: {}", e),
+            MineError::HttpStatus { url, status } => write!(f, "{} returned HTTP {}", url, status),
+            MineError::VersionNotFound { what, version, latest, suggestions } => write!(f, "{} {} not found{}", what, version, hint(latest, suggestions)),
+            MineError::BuildNotFound { version, build, latest, suggestions } => write!(f, "Build {} of version {} not found{}", build, version, hint(latest, suggestions)),
+            MineError::LoaderNotFound { loader, latest, suggestions } => write!(f, "Loader version {} not found{}", loader, hint(latest, suggestions)),
+            MineError::InstallerNotFound { installer, latest, suggestions } => write!(f, "Installer version {} not found{}", installer, hint(latest, suggestions)),
+            MineError::PluginNotFound { name, reason } => write!(f, "Plugin {} not found: {}", name, reason),
+            MineError::Parse { what, message } => write!(f, "Failed to parse {}: {}", what, message),
+            MineError::Checksum(e) => write!(f, "{}", e),
+            MineError::Io(e) => write!(f, "IO error: {}", e),
+            MineError::NotCached(what) => write!(f, "{} is not cached (run once without --offline to cache it)", what),
+            MineError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for MineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MineError::Network(e) => Some(e),
+            MineError::Checksum(e) => Some(e),
+            MineError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for MineError {
+    fn from(e: reqwest::Error) -> Self {
+        match (e.status(), e.url()) {
+            (Some(status), Some(url)) => MineError::HttpStatus { url: url.to_string(), status: status.as_u16() },
+            _ => MineError::Network(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for MineError {
+    fn from(e: std::io::Error) -> Self {
+        MineError::Io(e)
+    }
+}
+
+impl From<ChecksumError> for MineError {
+    fn from(e: ChecksumError) -> Self {
+        MineError::Checksum(e)
+    }
+}
+
+impl From<String> for MineError {
+    fn from(message: String) -> Self {
+        MineError::Other(message)
+    }
+}
+
+impl From<&str> for MineError {
+    fn from(message: &str) -> Self {
+        MineError::Other(message.to_string())
+    }
+}
@@ -1,9 +1,11 @@
 use std::io::Write;
+use std::path::Path;
 use chrono::prelude::*;
 use chrono_tz::Europe::Berlin;
 
-pub fn generate_eula() -> std::io::Result<()> {
-    let mut file = std::fs::File::create("eula.txt")?;
+// writes an accepted eula.txt into the server directory
+pub fn generate_eula(dir: &Path) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(dir.join("eula.txt"))?;
     let now = Utc::now().with_timezone(&Berlin);
     let formatted_date = now.format("%a %b %d %H:%M:%S %Z %Y").to_string();
     let content = format!("#By changing the setting below to TRUE you are indicating your agreement to our EULA (https://aka.ms/MinecraftEULA).\n#{date}\neula=true", date=formatted_date);
@@ -0,0 +1,68 @@
+//! Create reproducible Minecraft server configurations.
+//!
+//! The `mine` binary is a thin CLI on top of this crate: servers are resolved through a
+//! [`ServerProvider`] (see [`provider`] and [`providers`]), downloaded with
+//! [`downloader::download_file`] and the EULA is accepted with [`eula::generate_eula`].
+
+#[allow(clippy::module_inception)]
+pub mod server {
+    pub mod provider; // the ServerProvider trait and the list of providers
+    pub mod vanilla {
+        pub mod vanilla;
+    }
+    pub mod paper {
+        pub mod paper;
+    }
+    pub mod fabric {
+        pub mod fabric;
+        pub mod meta; // Fabric's meta API, shared with Quilt
+    }
+    pub mod quilt {
+        pub mod quilt;
+    }
+    pub mod forge {
+        pub mod forge;
+    }
+    pub mod neoforge {
+        pub mod neoforge;
+    }
+    pub mod bungeecord {
+        pub mod bungeecord;
+    }
+    pub mod spigot {
+        pub mod spigot;
+    }
+    pub mod sponge {
+        pub mod sponge;
+    }
+    pub mod arclight {
+        pub mod arclight;
+    }
+    pub mod bedrock {
+        pub mod bedrock;
+    }
+    pub mod java; // runs installers such as Forge's
+    pub mod maven; // maven-metadata.xml and checksums from maven repositories
+    pub mod proxy; // starter configurations for proxies
+}
+
+#[allow(clippy::module_inception)]
+pub mod plugins {
+    pub mod plugins;
+}
+
+pub mod cache; // the local download cache
+pub mod config; // upstream URLs and mirrors from the user config file
+pub mod downloader;
+pub mod error; // the MineError type
+pub mod eula; // an EULA file generator
+pub mod lock; // the mine.lock lockfile
+pub mod manifest; // the mine.toml manifest
+pub mod version; // a version parser
+pub mod warning; // non-fatal problems, reported by whoever uses the library
+
+pub use downloader::{download_file, Checksum, ChecksumError, DownloadedFile};
+pub use error::MineError;
+pub use manifest::{Manifest, Server};
+pub use server::provider::{provider, providers, ResolvedServer, ServerArtifact, ServerProvider};
+pub use version::{MinecraftVersion, Version};
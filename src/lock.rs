@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::downloader::ChecksumError;
+use crate::error::{MineError, Result};
+use crate::server::provider::ResolvedServer;
+
+pub const LOCK_FILE: &str = "mine.lock";
+
+// mine.lock, generated by `mine apply` and consumed by `mine install`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Lockfile {
+    // checksum of the mine.toml this lockfile was generated from
+    pub manifest: String,
+    // the server with every version resolved
+    pub server: ResolvedServer,
+    #[serde(default, rename = "artifact")]
+    pub artifacts: Vec<Artifact>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Artifact {
+    // "server" or the plugin name
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub path: String,
+    // empty for files built while installing, which cannot be downloaded
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub url: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl Artifact {
+    // a file the server's installer built, recorded by its hash
+    pub fn built(path: &str) -> Result<Self> {
+        let content = fs::read(artifact_path(path)?)?;
+        Ok(Artifact {
+            name: "server".to_string(),
+            version: None,
+            path: path.to_string(),
+            url: String::new(),
+            size: content.len() as u64,
+            sha256: format!("{:x}", Sha256::digest(&content)),
+        })
+    }
+
+    pub fn is_built(&self) -> bool {
+        self.url.is_empty()
+    }
+
+    // checks a built file against its recorded hash
+    pub fn verify(&self) -> Result<()> {
+        let actual = Artifact::built(&self.path)?.sha256;
+        if !actual.eq_ignore_ascii_case(&self.sha256) {
+            return Err(MineError::Checksum(ChecksumError { algorithm: "sha256", expected: self.sha256.clone(), actual }));
+        }
+        Ok(())
+    }
+}
+
+// an artifact path, which must stay inside the server directory: lockfiles are committed and
+// file names come from upstream, so neither may write to e.g. ../../.bashrc
+pub fn artifact_path(path: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(path);
+    let inside = path.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !inside || path.file_name().is_none() {
+        return Err(MineError::Other(format!("Artifact path {} is outside the server directory", path.display())));
+    }
+    Ok(path)
+}
+
+impl Lockfile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("Failed to read {}: {}", path.display(), e)))?;
+        let lockfile: Lockfile = toml::from_str(&content).map_err(|e| MineError::parse(path.display().to_string(), e))?;
+        for artifact in &lockfile.artifacts {
+            artifact_path(&artifact.path)?;
+        }
+        Ok(lockfile)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = toml::to_string_pretty(self).map_err(|e| MineError::parse(path.display().to_string(), e))?;
+        fs::write(path, format!("# This file is generated by mine. Do not edit it by hand.\n{}", content))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn artifact_paths_stay_in_the_server_directory() {
+        assert_eq!(artifact_path("server.jar").unwrap(), PathBuf::from("server.jar"));
+        assert_eq!(artifact_path("mods/spongeforge-1.12.2.jar").unwrap(), PathBuf::from("mods/spongeforge-1.12.2.jar"));
+        assert!(artifact_path("../../.bashrc").is_err());
+        assert!(artifact_path("plugins/../../x.jar").is_err());
+        assert!(artifact_path("/etc/passwd").is_err());
+        assert!(artifact_path("").is_err());
+        assert!(artifact_path(".").is_err());
+    }
+}
@@ -1,30 +1,20 @@
-use clap::{Parser, Subcommand};
-use indicatif::{ProgressBar, ProgressStyle, ProgressState};
+use clap::{Parser, Subcommand, ValueEnum};
+use chrono::{DateTime, Local};
+use indicatif::{HumanBytes, ProgressBar, ProgressDrawTarget, ProgressStyle, ProgressState};
 use tokio::sync::mpsc;
-use std::error::Error;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde_json::{json, Value};
 
-pub mod server {
-    pub mod vanilla {
-        pub mod vanilla;
-    }
-    pub mod paper {
-        pub mod paper;
-    }
-    pub mod fabric {
-        pub mod fabric;
-    }
-}
-
-pub mod plugins {
-    pub mod plugins;
-}
-
-pub mod downloader;
-pub mod eula; // an EULA file generator
-pub mod version; // a version parser
+use mine::{cache, config, downloader, eula, lock, manifest, plugins, server, warning};
+use mine::downloader::{Checksum, DownloadedFile};
+use mine::error::{MineError, Result};
+use mine::lock::{Artifact, Lockfile};
+use mine::manifest::{Backend, Manifest, Server};
+use mine::server::provider::{ResolvedServer, ServerProvider};
+use mine::version::VersionReq;
 
 /// Simple program to initialize a Minecraft server
 #[derive(Parser, Debug)]
@@ -33,6 +23,26 @@ pub mod version; // a version parser
 struct Args {
     #[clap(subcommand)]
     command: Command,
+
+    /// Only use cached manifests and downloads
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Output format; json prints a single JSON document without colours or progress bars
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+fn json_output() -> bool {
+    JSON_OUTPUT.load(Ordering::Relaxed)
 }
 
 #[derive(Subcommand, Debug)]
@@ -42,17 +52,93 @@ enum Command {
         #[clap(subcommand)]
         server: ServerCommand,
     },
+    /// Bring the current directory in line with mine.toml
+    Apply,
+    /// Install the server pinned in mine.lock (resolving mine.toml if there is no lockfile)
+    Install {
+        /// Fail instead of resolving if mine.lock is missing or out of date
+        #[arg(long)]
+        locked: bool,
+    },
     /// Plugin management
     Plugin {
         #[clap(subcommand)]
         plugin: PluginCommand,
     },
+    /// Manage the local download cache
+    Cache {
+        #[clap(subcommand)]
+        cache: CacheCommand,
+    },
+    /// List the versions available for a server type
+    Versions(VersionsArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct VersionsArgs {
+    /// Server type (e.g. vanilla, paper or fabric)
+    server: String,
+
+    /// Include snapshots and unstable loader or installer versions
+    #[arg(long)]
+    snapshots: bool,
+
+    /// List the builds of this Minecraft version instead
+    #[arg(long, value_name = "MC", group = "list")]
+    builds: Option<String>,
+
+    /// List loader versions instead (of a Minecraft version, for loaders such as Forge that depend on it)
+    #[arg(long, value_name = "MC", num_args = 0..=1, default_missing_value = "", group = "list")]
+    loaders: Option<String>,
+
+    /// List installer versions instead
+    #[arg(long, group = "list")]
+    installers: bool,
+
+    /// Only show versions matching a constraint or prefix (e.g. ">=1.20" or 1.20)
+    #[arg(long)]
+    filter: Option<String>,
+
+    /// Order of the list
+    #[arg(long, value_enum, default_value_t = SortOrder::Newest)]
+    sort: SortOrder,
+
+    /// Show at most this many versions
+    #[arg(long)]
+    limit: Option<usize>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum SortOrder {
+    Newest,
+    Oldest,
+}
+
+// where Fabric versions come from, which is also the server type written to mine.toml
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum FabricMeta {
+    /// meta.fabricmc.net, for 1.14 and newer
+    Fabric,
+    /// meta.legacyfabric.net, for 1.13.2 and older
+    LegacyFabric,
+    /// Babric's meta, for beta 1.7.3
+    Babric,
+}
+
+impl FabricMeta {
+    fn kind(self) -> &'static str {
+        match self {
+            FabricMeta::Fabric => "fabric",
+            FabricMeta::LegacyFabric => "legacy-fabric",
+            FabricMeta::Babric => "babric",
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
 enum ServerCommand {
     Vanilla {
-        /// Minecraft version to use
+        /// Minecraft version or constraint to use (e.g. 1.21 or ">=1.20.4, <1.21")
         #[arg(default_value = "")]
         version: String,
 
@@ -64,29 +150,66 @@ enum ServerCommand {
         #[arg(long)]
         eula: bool,
     },
-    Paper {
-        /// Minecraft version to use
+    Paper(PaperArgs),
+    Folia(PaperArgs),
+    Purpur(PaperArgs),
+    Velocity(ProxyArgs),
+    Waterfall(ProxyArgs),
+    #[command(name = "bungeecord")]
+    BungeeCord {
+        /// Jenkins build number or constraint to use (e.g. 1880 or ">=1850")
         #[arg(default_value = "")]
         version: String,
 
-        /// Build number
+        /// Backend server listed in config.yml, as name=address (repeatable)
+        #[arg(long = "backend", value_name = "NAME=ADDRESS", value_parser = parse_backend)]
+        backends: Vec<Backend>,
+    },
+    Spigot(BuildToolsArgs),
+    #[command(name = "spongevanilla")]
+    SpongeVanilla(SpongeArgs),
+    #[command(name = "spongeforge")]
+    SpongeForge(SpongeArgs),
+    Mohist(PaperArgs),
+    Banner(PaperArgs),
+    Arclight {
+        /// Minecraft version or constraint to use (e.g. 1.20.1 or ">=1.20, <1.21")
+        #[arg(default_value = "")]
+        version: String,
+
+        /// Mod loader the build is for: forge, neoforge or fabric (default: the first one available)
+        #[arg(long, default_value = "")]
+        loader: String,
+
+        /// Build or constraint (e.g. 1.0.5, ^1.0 or latest-stable)
         #[arg(long, default_value = None)]
-        build: Option<u32>,
+        build: Option<String>,
 
         /// Accept the Mojang EULA
         #[arg(long)]
         eula: bool,
     },
+    Bedrock {
+        /// Bedrock version or constraint to use (e.g. 1.21.2.02)
+        #[arg(default_value = "")]
+        version: String,
+
+        /// Use the latest preview version
+        #[arg(long)]
+        preview: bool,
+    },
+    #[command(name = "craftbukkit")]
+    CraftBukkit(BuildToolsArgs),
     Fabric {
-        /// Minecraft version to use
+        /// Minecraft version or constraint to use (e.g. 1.21 or ">=1.20.4, <1.21")
         #[arg(default_value = "")]
         version: String,
 
-        /// Loader version
+        /// Loader version or constraint (e.g. 0.15.11 or ^0.15)
         #[arg(long, default_value = "")]
         loader: String,
 
-        /// Installer version
+        /// Installer version or constraint
         #[arg(long, default_value = "")]
         installer: String,
 
@@ -98,12 +221,170 @@ enum ServerCommand {
         #[arg(long)]
         unstable_installer: bool,
 
+        /// Metadata source to resolve versions from
+        #[arg(long, value_enum, default_value_t = FabricMeta::Fabric)]
+        meta: FabricMeta,
+
+        /// Accept the Mojang EULA
+        #[arg(long)]
+        eula: bool,
+    },
+    Quilt {
+        /// Minecraft version or constraint to use (e.g. 1.21 or ">=1.20.4, <1.21")
+        #[arg(default_value = "")]
+        version: String,
+
+        /// Loader version or constraint (e.g. 0.26.0 or ^0.26)
+        #[arg(long, default_value = "")]
+        loader: String,
+
+        /// Installer version or constraint
+        #[arg(long, default_value = "")]
+        installer: String,
+
+        /// Use beta loader versions
+        #[arg(long)]
+        unstable_loader: bool,
+
+        /// Use beta installer versions
+        #[arg(long)]
+        unstable_installer: bool,
+
+        /// Accept the Mojang EULA
+        #[arg(long)]
+        eula: bool,
+    },
+    Forge {
+        /// Minecraft version or constraint to use (e.g. 1.20.1 or ">=1.20, <1.21")
+        #[arg(default_value = "")]
+        version: String,
+
+        /// Forge version or constraint (e.g. 47.3.0, ^47 or latest-stable for the recommended one)
+        #[arg(long, default_value = "")]
+        forge: String,
+
+        /// Accept the Mojang EULA
+        #[arg(long)]
+        eula: bool,
+    },
+    #[command(name = "neoforge")]
+    NeoForge {
+        /// Minecraft version or constraint to use (e.g. 1.21 or ">=1.20.4, <1.21")
+        #[arg(default_value = "")]
+        version: String,
+
+        /// NeoForge version or constraint (e.g. 21.0.167 or ^21.0)
+        #[arg(long, default_value = "")]
+        neoforge: String,
+
+        /// Allow beta NeoForge versions
+        #[arg(long)]
+        unstable_loader: bool,
+
         /// Accept the Mojang EULA
         #[arg(long)]
         eula: bool,
     },
 }
 
+// Paper and the other projects served by the PaperMC (or Purpur) API, and MohistMC's which mirrors it
+#[derive(clap::Args, Debug)]
+struct PaperArgs {
+    /// Version or constraint to use (e.g. 1.21 or ">=1.20.4, <1.21")
+    #[arg(default_value = "")]
+    version: String,
+
+    /// Build number or constraint (e.g. 123, ">=400" or latest-stable)
+    #[arg(long, default_value = None)]
+    build: Option<String>,
+
+    /// Accept the Mojang EULA
+    #[arg(long)]
+    eula: bool,
+}
+
+impl PaperArgs {
+    fn into_manifest(self, kind: &str) -> (Server, bool) {
+        (Server { kind: kind.to_string(), version: self.version, build: self.build, ..Default::default() }, self.eula)
+    }
+}
+
+// Velocity and Waterfall, which take no EULA but list backend servers in their configuration
+#[derive(clap::Args, Debug)]
+struct ProxyArgs {
+    /// Version or constraint to use (e.g. 3.3.0-SNAPSHOT or 1.21)
+    #[arg(default_value = "")]
+    version: String,
+
+    /// Build number or constraint (e.g. 123, ">=400" or latest-stable)
+    #[arg(long, default_value = None)]
+    build: Option<String>,
+
+    /// Backend server listed in the proxy configuration, as name=address (repeatable)
+    #[arg(long = "backend", value_name = "NAME=ADDRESS", value_parser = parse_backend)]
+    backends: Vec<Backend>,
+}
+
+impl ProxyArgs {
+    fn into_manifest(self, kind: &str) -> (Server, bool) {
+        (Server { kind: kind.to_string(), version: self.version, build: self.build, backends: self.backends, ..Default::default() }, false)
+    }
+}
+
+// Spigot and CraftBukkit, which are compiled with BuildTools
+#[derive(clap::Args, Debug)]
+struct BuildToolsArgs {
+    /// Minecraft version or constraint to use (e.g. 1.21 or ">=1.20.4, <1.21")
+    #[arg(default_value = "")]
+    version: String,
+
+    /// Accept the Mojang EULA
+    #[arg(long)]
+    eula: bool,
+}
+
+impl BuildToolsArgs {
+    fn into_manifest(self, kind: &str) -> (Server, bool) {
+        (Server { kind: kind.to_string(), version: self.version, ..Default::default() }, self.eula)
+    }
+}
+
+// SpongeVanilla and SpongeForge
+#[derive(clap::Args, Debug)]
+struct SpongeArgs {
+    /// Minecraft version or constraint to use (e.g. 1.20.6 or ">=1.16.5, <1.21")
+    #[arg(default_value = "")]
+    version: String,
+
+    /// Sponge API version or constraint (e.g. 11, 8.2.0 or ^8)
+    #[arg(long, default_value = "")]
+    api: String,
+
+    /// Allow builds not marked recommended, such as release candidates
+    #[arg(long)]
+    unstable: bool,
+
+    /// Accept the Mojang EULA
+    #[arg(long)]
+    eula: bool,
+}
+
+impl SpongeArgs {
+    fn into_manifest(self, kind: &str) -> (Server, bool) {
+        (Server { kind: kind.to_string(), version: self.version, loader: self.api, unstable_loader: self.unstable, ..Default::default() }, self.eula)
+    }
+}
+
+// lobby=127.0.0.1:25566
+fn parse_backend(s: &str) -> std::result::Result<Backend, String> {
+    match s.split_once('=') {
+        Some((name, address)) if !name.is_empty() && !address.is_empty() => {
+            Ok(Backend { name: name.to_string(), address: address.to_string() })
+        },
+        _ => Err(format!("expected name=address, got \"{}\"", s)),
+    }
+}
+
 #[derive(Subcommand, Debug)]
 enum PluginCommand{
     Install {
@@ -113,141 +394,434 @@ enum PluginCommand{
     },
 }
 
-// convert ServerType to string impl
+#[derive(Subcommand, Debug)]
+enum CacheCommand {
+    /// List cached downloads
+    Ls,
+    /// Remove downloads that have not been used for a while
+    Prune {
+        /// Remove downloads unused for this many days
+        #[arg(long, default_value_t = 30)]
+        days: u64,
+    },
+    /// Remove everything from the cache
+    Clear,
+}
+
 impl ServerCommand {
-    fn to_string(&self) -> String {
+    fn into_manifest(self) -> (Server, bool) {
         match self {
-            ServerCommand::Vanilla { .. } => "Vanilla".to_string(),
-            ServerCommand::Paper { .. } => "Paper".to_string(),
-            ServerCommand::Fabric { .. } => "Fabric".to_string(),
+            ServerCommand::Vanilla { version, snapshot, eula } => {
+                (Server { kind: "vanilla".to_string(), version, snapshot, ..Default::default() }, eula)
+            },
+            ServerCommand::Paper(args) => args.into_manifest("paper"),
+            ServerCommand::Folia(args) => args.into_manifest("folia"),
+            ServerCommand::Purpur(args) => args.into_manifest("purpur"),
+            ServerCommand::Velocity(args) => args.into_manifest("velocity"),
+            ServerCommand::Waterfall(args) => args.into_manifest("waterfall"),
+            ServerCommand::BungeeCord { version, backends } => {
+                (Server { kind: "bungeecord".to_string(), version, backends, ..Default::default() }, false)
+            },
+            ServerCommand::Spigot(args) => args.into_manifest("spigot"),
+            ServerCommand::SpongeVanilla(args) => args.into_manifest("spongevanilla"),
+            ServerCommand::SpongeForge(args) => args.into_manifest("spongeforge"),
+            ServerCommand::Mohist(args) => args.into_manifest("mohist"),
+            ServerCommand::Banner(args) => args.into_manifest("banner"),
+            ServerCommand::Arclight { version, loader, build, eula } => {
+                (Server { kind: "arclight".to_string(), version, loader, build, ..Default::default() }, eula)
+            },
+            ServerCommand::Bedrock { version, preview } => {
+                (Server { kind: "bedrock".to_string(), version, snapshot: preview, ..Default::default() }, false)
+            },
+            ServerCommand::CraftBukkit(args) => args.into_manifest("craftbukkit"),
+            ServerCommand::Fabric { version, loader, installer, unstable_loader, unstable_installer, meta, eula } => {
+                (Server { kind: meta.kind().to_string(), version, loader, installer, unstable_loader, unstable_installer, ..Default::default() }, eula)
+            },
+            ServerCommand::Quilt { version, loader, installer, unstable_loader, unstable_installer, eula } => {
+                (Server { kind: "quilt".to_string(), version, loader, installer, unstable_loader, unstable_installer, ..Default::default() }, eula)
+            },
+            ServerCommand::Forge { version, forge, eula } => {
+                (Server { kind: "forge".to_string(), version, loader: forge, ..Default::default() }, eula)
+            },
+            ServerCommand::NeoForge { version, neoforge, unstable_loader, eula } => {
+                (Server { kind: "neoforge".to_string(), version, loader: neoforge, unstable_loader, ..Default::default() }, eula)
+            },
         }
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    let args = Args::parse();
+async fn download(url: String, path: PathBuf, checksum: Option<Checksum>) -> Result<DownloadedFile> {
+    let (progress_tx, mut progress_rx) = mpsc::channel(100);
+    let (length_tx, mut length_rx) = mpsc::channel(1);
+
+    let handle = tokio::spawn(async move {
+        downloader::download_file(&url, &path, checksum.as_ref(), progress_tx, length_tx).await
+    });
+
+    let total_bytes = match length_rx.recv().await {
+        Some(Some(bytes)) => bytes,
+        Some(None) | None => 0,
+    };
+
+    let pb = if total_bytes > 0 {
+        // Progress bar for known content length
+        ProgressBar::new(total_bytes).with_style(
+            ProgressStyle::with_template(
+                "{spinner:.green} {msg} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})"
+            ).unwrap()
+            .with_key("eta", |state: &ProgressState, w: &mut dyn Write| {
+                write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap()
+            })
+            .progress_chars("#>-")
+        )
+    } else {
+        // Spinner for unknown content length
+        ProgressBar::new_spinner().with_style(
+            ProgressStyle::with_template("{spinner:.green} {msg}").unwrap()
+        )
+    };
+
+    if json_output() {
+        pb.set_draw_target(ProgressDrawTarget::hidden());
+    }
+    pb.set_message("Downloading...");
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    while let Some(downloaded) = progress_rx.recv().await {
+        pb.set_position(downloaded);
+    }
+
+    pb.finish_and_clear();
+
+    handle.await.map_err(|e| MineError::Other(format!("Download task failed: {}", e)))?
+}
+
+fn start_spinner() -> ProgressBar {
     let pb = ProgressBar::new_spinner();
-    
-    match args.command {
-        Command::Init { server } => {
-            println!("\x1b[33mHint: use --help to see available options!\x1b[0m");
-            
-            pb.set_style(ProgressStyle::with_template("{spinner:.green} {msg}").unwrap());
-            pb.enable_steady_tick(Duration::from_millis(100));
-            pb.set_message("Working...");
-            
-            let eula_accepted = match server {
-                ServerCommand::Vanilla { eula, .. } => eula,
-                ServerCommand::Paper { eula, .. } => eula,
-                ServerCommand::Fabric { eula, .. } => eula,
-            };
+    pb.set_style(ProgressStyle::with_template("{spinner:.green} {msg}").unwrap());
+    if json_output() {
+        pb.set_draw_target(ProgressDrawTarget::hidden());
+    }
+    pb.enable_steady_tick(Duration::from_millis(100));
+    pb.set_message("Working...");
+    pb
+}
 
-            if eula_accepted {
-                if let Err(e) = eula::generate_eula() {
-                    eprintln!("Error generating EULA: {}", e);
-                }
-            }
+// proxies have no EULA, so eula = true is ignored for them
+fn accept_eula(manifest: &Manifest, provider: &dyn ServerProvider) {
+    if manifest.eula && provider.needs_eula() {
+        if let Err(e) = eula::generate_eula(Path::new(".")) {
+            eprintln!("Error generating EULA: {}", e);
+        }
+    }
+}
 
-            let version_info;
-            let download_link = match server {
-                ServerCommand::Vanilla { ref version, snapshot, .. } => {
-                    let result = server::vanilla::vanilla::get_download_link(Some(version.clone()), snapshot).await;
-                    match result {
-                        Ok((link, ver_info)) => {
-                            version_info = ver_info;
-                            link
-                        },
-                        Err(e) => {
-                            eprintln!("Error: {}", e);
-                            return Err(e.into());
-                        },
-                    }
-                },
-                ServerCommand::Paper { ref version, build, .. } => {
-                    let result = server::paper::paper::get_download_link(Some(version.clone()), build).await;
-                    match result {
-                        Ok((link, ver_info)) => {
-                            version_info = ver_info;
-                            link
-                        },
-                        Err(e) => {
-                            eprintln!("Error: {}", e);
-                            return Err(e.into());
-                        },
-                    }
-                },
-                ServerCommand::Fabric { ref version, ref loader, ref installer, unstable_loader, unstable_installer, .. } => {
-                    let result = server::fabric::fabric::get_download_link(Some(version.clone()), Some(loader.clone()), Some(installer.clone()), unstable_loader, unstable_installer).await;
-                    match result {
-                        Ok((link, ver_info)) => {
-                            version_info = ver_info;
-                            link
-                        },
-                        Err(e) => {
-                            eprintln!("Error: {}", e);
-                            return Err(e.into());
-                        },
-                    }
-                },
-            };
+// runs the provider's setup once the server is downloaded, e.g. the Forge installer
+async fn install_server(provider: &dyn ServerProvider, server: &Server, resolved: &ResolvedServer) -> Result<()> {
+    let pb = start_spinner();
+    // installers print long lines, which are cut to the terminal width
+    pb.set_style(ProgressStyle::with_template("{spinner:.green} {wide_msg}").unwrap());
+    pb.set_message(format!("Installing {} server...", provider.name()));
+    let progress = |line: &str| pb.set_message(format!("Installing {} server: {}", provider.name(), line));
+    let result = provider.install(server, resolved, Path::new("."), &progress).await;
+    pb.finish_and_clear();
+    result
+}
+
+// brings the current directory in line with the manifest and writes mine.lock
+async fn apply(manifest: &Manifest) -> Result<Lockfile> {
+    let pb = start_spinner();
 
+    let provider = server::provider::provider(&manifest.server.kind)?;
+    accept_eula(manifest, provider.as_ref());
+
+    let resolved = async {
+        let resolved = provider.resolve(&manifest.server).await?;
+        let artifacts = provider.artifacts(&resolved).await?;
+        Ok::<_, MineError>((resolved, artifacts))
+    }.await;
+
+    let (resolved, server_artifacts) = match resolved {
+        Ok(result) => result,
+        Err(e) => {
             pb.finish_and_clear();
+            return Err(e);
+        },
+    };
 
-            let (progress_tx, mut progress_rx) = mpsc::channel(100);
-            let (length_tx, mut length_rx) = mpsc::channel(1);
+    pb.finish_and_clear();
 
-            tokio::spawn(async move {
-                if let Err(e) = downloader::download_file(&download_link, Path::new("server.jar"), progress_tx, length_tx).await {
-                    eprintln!("\x1b[31mDownload error: {}\x1b[0m", e);
-                }
+    let mut artifacts = Vec::new();
+    for artifact in server_artifacts {
+        let path = lock::artifact_path(&artifact.path)?;
+        // e.g. SpongeForge, which goes into mods/
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let downloaded = download(artifact.url.clone(), path, artifact.checksum).await?;
+        artifacts.push(Artifact {
+            name: "server".to_string(),
+            version: None,
+            path: artifact.path,
+            url: artifact.url,
+            size: downloaded.size,
+            sha256: downloaded.sha256,
+        });
+    }
+    install_server(provider.as_ref(), &manifest.server, &resolved).await?;
+    for path in provider.built_artifacts() {
+        artifacts.push(Artifact::built(path)?);
+    }
+
+    if !manifest.plugins.is_empty() {
+        let (loader, directory) = provider.plugin_target()
+            .ok_or_else(|| MineError::Other(format!("{} servers do not support plugins", provider.name())))?;
+        std::fs::create_dir_all(directory)?;
+
+        let game_version = Some(resolved.version.as_str());
+        for name in &manifest.plugins {
+            let plugin = plugins::plugins::get_download_link(name, loader, game_version).await?;
+            let path = format!("{}/{}", directory, plugin.file_name);
+            let downloaded = download(plugin.url.clone(), lock::artifact_path(&path)?, plugin.checksum).await?;
+            if !json_output() {
+                println!("Installed plugin {} ({})", name, plugin.file_name);
+            }
+            artifacts.push(Artifact {
+                name: name.clone(),
+                version: Some(plugin.version),
+                path,
+                url: plugin.url,
+                size: downloaded.size,
+                sha256: downloaded.sha256,
             });
+        }
+    }
 
-            let total_bytes = match length_rx.recv().await {
-                Some(Some(bytes)) => bytes,
-                Some(None) | None => 0,
-            };
+    let lockfile = Lockfile {
+        manifest: manifest.checksum()?,
+        server: resolved,
+        artifacts,
+    };
+    lockfile.save(Path::new(lock::LOCK_FILE))?;
+
+    if !json_output() {
+        println!("\x1b[32mSuccessfully initialized {} {} server!\x1b[0m", provider.name(), lockfile.server.version_info());
+    }
+    Ok(lockfile)
+}
+
+// downloads exactly the artifacts pinned in mine.lock
+async fn install_locked(manifest: &Manifest, lockfile: Lockfile) -> Result<Lockfile> {
+    let provider = server::provider::provider(&lockfile.server.kind)?;
+    accept_eula(manifest, provider.as_ref());
+
+    for artifact in lockfile.artifacts.iter().filter(|artifact| !artifact.is_built()) {
+        let path = lock::artifact_path(&artifact.path)?;
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let checksum = Checksum::Sha256(artifact.sha256.clone());
+        if let Err(e) = download(artifact.url.clone(), path, Some(checksum)).await {
+            if let (MineError::Checksum(_), false) = (&e, json_output()) {
+                eprintln!("{} does not match {}", artifact.path, lock::LOCK_FILE);
+            }
+            return Err(e);
+        }
+    }
+
+    install_server(provider.as_ref(), &manifest.server, &lockfile.server).await?;
+
+    // e.g. a Spigot jar that BuildTools built differently
+    for artifact in lockfile.artifacts.iter().filter(|artifact| artifact.is_built()) {
+        if let Err(e) = artifact.verify() {
+            if let (MineError::Checksum(_), false) = (&e, json_output()) {
+                eprintln!("{} does not match {}", artifact.path, lock::LOCK_FILE);
+            }
+            return Err(e);
+        }
+    }
+
+    if !json_output() {
+        println!("\x1b[32mSuccessfully installed {} {} server from {}!\x1b[0m", provider.name(), lockfile.server.version_info(), lock::LOCK_FILE);
+    }
+    Ok(lockfile)
+}
+
+// the --output json document for a command that installed a server
+fn server_document(lockfile: &Lockfile) -> Result<Value> {
+    let provider = server::provider::provider(&lockfile.server.kind)?;
+    Ok(json!({
+        "name": provider.name(),
+        "server": lockfile.server,
+        "artifacts": lockfile.artifacts,
+        "lockfile": lock::LOCK_FILE,
+    }))
+}
+
+// lists versions, builds, loaders or installers of a server type
+async fn versions(args: VersionsArgs) -> Result<Value> {
+    let VersionsArgs { server, snapshots, builds, loaders, installers, filter, sort, limit } = args;
+
+    let provider = server::provider::provider(&server)?;
+    let mut list = if let Some(version) = builds {
+        provider.list_builds(&version).await?.iter().map(u32::to_string).collect()
+    } else if let Some(version) = loaders {
+        provider.list_loaders(&version, snapshots).await?
+    } else if installers {
+        provider.list_installers(snapshots).await?
+    } else {
+        provider.list_versions(snapshots).await?
+    };
+
+    if let Some(filter) = filter {
+        let req = server::provider::requirement("filter", &filter)?;
+        list.retain(|version| match &req {
+            VersionReq::Exact(prefix) => version.starts_with(prefix.as_str()),
+            req => req.matches(version),
+        });
+    }
+
+    // providers list newest first
+    if let SortOrder::Oldest = sort {
+        list.reverse();
+    }
+    if let Some(limit) = limit {
+        list.truncate(limit);
+    }
+
+    if !json_output() {
+        for version in &list {
+            println!("{}", version);
+        }
+    }
+    Ok(json!(list))
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
 
-            let pb = if total_bytes > 0 {
-                // Progress bar for known content length
-                ProgressBar::new(total_bytes).with_style(
-                    ProgressStyle::with_template(
-                        "{spinner:.green} {msg} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})"
-                    )?
-                    .with_key("eta", |state: &ProgressState, w: &mut dyn Write| {
-                        write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap()
-                    })
-                    .progress_chars("#>-")
-                )
+    JSON_OUTPUT.store(args.output == OutputFormat::Json, Ordering::Relaxed);
+    // on stderr, so stdout only ever has the JSON document
+    warning::set_handler(|message| match json_output() {
+        true => eprintln!("{}", json!({ "warning": message })),
+        false => eprintln!("{}", message),
+    });
+
+    match run(args).await {
+        Ok(document) => {
+            if json_output() {
+                println!("{:#}", document);
+            }
+        },
+        Err(e) => {
+            if json_output() {
+                println!("{:#}", json!({ "error": { "code": e.exit_code(), "message": e.to_string() } }));
             } else {
-                // Spinner for unknown content length
-                ProgressBar::new_spinner().with_style(
-                    ProgressStyle::with_template("{spinner:.green} {msg}").unwrap()
-                )
-            };
+                eprintln!("\x1b[31mError: {}\x1b[0m", e);
+            }
+            std::process::exit(e.exit_code());
+        },
+    }
+}
 
-            pb.set_message("Downloading...");
-            pb.enable_steady_tick(Duration::from_millis(100));
+// runs a command, returning the document printed with --output json
+async fn run(args: Args) -> Result<Value> {
+    cache::set_offline(args.offline);
+    config::init()?;
+    let manifest_path = Path::new(manifest::MANIFEST_FILE);
 
-            while let Some(downloaded) = progress_rx.recv().await {
-                pb.set_position(downloaded);
+    match args.command {
+        Command::Init { server } => {
+            if !json_output() {
+                println!("\x1b[33mHint: use --help to see available options!\x1b[0m");
             }
 
-            pb.finish_and_clear();
+            // keep the plugin list of an existing manifest
+            let plugins = if manifest_path.exists() {
+                Manifest::load(manifest_path)?.plugins
+            } else {
+                Vec::new()
+            };
+
+            let (server, eula) = server.into_manifest();
+            let manifest = Manifest { eula, plugins, server };
 
-            println!("\x1b[32mSuccessfully initialized {} {} server!\x1b[0m", server.to_string(), version_info);
+            // an existing mine.toml is only replaced by one that resolves
+            let lockfile = apply(&manifest).await?;
+            manifest.save(manifest_path)?;
+            server_document(&lockfile)
+        },
+        Command::Apply => {
+            let manifest = Manifest::load(manifest_path)?;
+            server_document(&apply(&manifest).await?)
+        },
+        Command::Install { locked } => {
+            let manifest = Manifest::load(manifest_path)?;
+            let lock_path = Path::new(lock::LOCK_FILE);
+            let lockfile = if lock_path.exists() { Some(Lockfile::load(lock_path)?) } else { None };
+
+            let lockfile = match lockfile {
+                Some(lockfile) if lockfile.manifest == manifest.checksum()? => install_locked(&manifest, lockfile).await?,
+                Some(_) if locked => return Err(MineError::Other(format!("{} is out of date with {}, run `mine apply` to update it", lock::LOCK_FILE, manifest::MANIFEST_FILE))),
+                None if locked => return Err(MineError::Other(format!("{} not found, run `mine apply` to generate it", lock::LOCK_FILE))),
+                _ => apply(&manifest).await?,
+            };
+            server_document(&lockfile)
         },
         Command::Plugin { plugin } => {
             match plugin {
                 PluginCommand::Install { name } => {
                     let plugin = plugins::plugins::search_plugin(name.to_string()).await?;
-                    println!("Plugin: {}", plugin.title);
-                    println!("Description: {}", plugin.description);
-                    println!("Downloads: {}", plugin.downloads);
+                    if !json_output() {
+                        println!("Plugin: {}", plugin.title);
+                        println!("Description: {}", plugin.description);
+                        println!("Downloads: {}", plugin.downloads);
+                    }
+                    Ok(json!({ "title": plugin.title, "description": plugin.description, "downloads": plugin.downloads }))
+                },
+            }
+        },
+        Command::Cache { cache } => {
+            match cache {
+                CacheCommand::Ls => {
+                    let entries = cache::entries()?;
+                    if entries.is_empty() && !json_output() {
+                        println!("The cache is empty");
+                    }
+                    let mut documents = Vec::new();
+                    for entry in entries {
+                        let last_used: DateTime<Local> = entry.last_used.into();
+                        if !json_output() {
+                            let urls = if entry.urls.is_empty() { "-".to_string() } else { entry.urls.join(", ") };
+                            println!("{}  {:>10}  {}  {}", &entry.sha256[..12], HumanBytes(entry.size).to_string(), last_used.format("%Y-%m-%d"), urls);
+                        }
+                        documents.push(json!({
+                            "sha256": entry.sha256,
+                            "size": entry.size,
+                            "last_used": last_used.to_rfc3339(),
+                            "urls": entry.urls,
+                        }));
+                    }
+                    Ok(json!(documents))
+                },
+                CacheCommand::Prune { days } => {
+                    let (removed, freed) = cache::prune(Duration::from_secs(days * 24 * 60 * 60))?;
+                    if !json_output() {
+                        println!("Removed {} cached downloads ({})", removed, HumanBytes(freed));
+                    }
+                    Ok(json!({ "removed": removed, "freed": freed }))
+                },
+                CacheCommand::Clear => {
+                    cache::clear()?;
+                    if !json_output() {
+                        println!("Cleared the cache");
+                    }
+                    Ok(json!({ "cleared": true }))
                 },
             }
         },
+        Command::Versions(args) => versions(args).await,
     }
-
-    Ok(())
 }
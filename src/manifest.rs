@@ -0,0 +1,101 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
+use std::io;
+use std::path::Path;
+
+use crate::error::{MineError, Result};
+
+pub const MANIFEST_FILE: &str = "mine.toml";
+
+// mine.toml
+//
+// eula = true
+// plugins = ["worldedit"]
+//
+// [server]
+// type = "paper"
+// version = "1.21"
+// build = 123 (or "latest-stable", ">=400", ...)
+//
+// [[server.backends]] (proxies only)
+// name = "lobby"
+// address = "127.0.0.1:25566"
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Manifest {
+    #[serde(default)]
+    pub eula: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plugins: Vec<String>,
+    pub server: Server,
+}
+
+// the server to install; an empty version (or loader/installer) means "latest".
+// Versions, loaders, installers and builds may also be constraints such as ">=1.20.4, <1.21", see version::VersionReq.
+// Which fields apply depends on the server type, see server::provider
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Server {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub snapshot: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "number_or_string")]
+    pub build: Option<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub loader: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub installer: String,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub unstable_loader: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub unstable_installer: bool,
+    // the servers behind a proxy, written into its starter configuration
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backends: Vec<Backend>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Backend {
+    pub name: String,
+    pub address: String,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+// accepts both `build = 123` and `build = ">=400"`
+fn number_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(u64),
+        String(String),
+    }
+
+    Ok(match NumberOrString::deserialize(deserializer)? {
+        NumberOrString::Number(number) => Some(number.to_string()),
+        NumberOrString::String(string) => Some(string),
+    })
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("Failed to read {}: {}", path.display(), e)))?;
+        toml::from_str(&content).map_err(|e| MineError::parse(path.display().to_string(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = toml::to_string_pretty(self).map_err(|e| MineError::parse(path.display().to_string(), e))?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    // sha256 of the normalized manifest, used to tell whether mine.lock is out of date
+    pub fn checksum(&self) -> Result<String> {
+        let content = toml::to_string(self).map_err(|e| MineError::parse(MANIFEST_FILE, e))?;
+        Ok(format!("{:x}", Sha256::digest(content.as_bytes())))
+    }
+}
@@ -1,10 +1,96 @@
 // Modrinth API
 
-use modrinth_api::models::Project;
+use modrinth_api::apis::configuration::Configuration;
+use modrinth_api::apis::Error as ModrinthError;
+use modrinth_api::models::{Project, Version};
+use std::future::Future;
 
-pub async fn search_plugin(name: String) -> Result<Project, Box<dyn std::error::Error>> {
-    let configuration = modrinth_api::apis::configuration::Configuration::new();
+use crate::cache;
+use crate::config;
+use crate::downloader::Checksum;
+use crate::error::{MineError, Result};
+use crate::warning;
 
-    let project = modrinth_api::apis::projects_api::get_project(&configuration, &name).await.unwrap();
-    Ok(project)
-}
\ No newline at end of file
+pub struct PluginFile {
+    pub url: String,
+    pub file_name: String,
+    pub version: String,
+    pub checksum: Option<Checksum>,
+}
+
+fn modrinth_error<T>(name: &str, error: ModrinthError<T>) -> MineError {
+    match error {
+        ModrinthError::ResponseError(response) if response.status == reqwest::StatusCode::NOT_FOUND => {
+            MineError::PluginNotFound { name: name.to_string(), reason: "no such Modrinth project".to_string() }
+        },
+        ModrinthError::ResponseError(response) => {
+            MineError::HttpStatus { url: format!("Modrinth project {}", name), status: response.status.as_u16() }
+        },
+        ModrinthError::Reqwest(e) => e.into(),
+        ModrinthError::Serde(e) => MineError::parse(format!("Modrinth project {}", name), e),
+        ModrinthError::Io(e) => MineError::Io(e),
+        ModrinthError::InvalidHeaderValue(e) => MineError::Other(e.to_string()),
+    }
+}
+
+// calls the Modrinth API at its configured URL, then at its mirrors until one answers;
+// a missing project is an answer
+async fn modrinth<T, E, F, Fut>(name: &str, call: F) -> Result<T>
+where
+    F: Fn(Configuration) -> Fut,
+    Fut: Future<Output = std::result::Result<T, ModrinthError<E>>>,
+{
+    let mut candidates = config::urls(Configuration::new().base_path.as_str()).into_iter().peekable();
+    while let Some(base_path) = candidates.next() {
+        let configuration = Configuration { base_path, ..Configuration::new() };
+        let error = match call(configuration).await {
+            Ok(result) => return Ok(result),
+            Err(e) => modrinth_error(name, e),
+        };
+        match candidates.peek() {
+            Some(next) if !matches!(error, MineError::PluginNotFound { .. }) => warning::warn(&format!("{}, trying {}", error, next)),
+            _ => return Err(error),
+        }
+    }
+    Err(MineError::Other(format!("No URL to look up Modrinth project {} at", name)))
+}
+
+pub async fn search_plugin(name: String) -> Result<Project> {
+    let name = name.as_str();
+    modrinth(name, |configuration| async move {
+        modrinth_api::apis::projects_api::get_project(&configuration, name).await
+    }).await
+}
+
+// returns the newest plugin file matching the loader and game version
+pub async fn get_download_link(name: &str, loader: &str, game_version: Option<&str>) -> Result<PluginFile> {
+    // fetched like the other upstream APIs, so it is cached for --offline and uses the mirrors
+    let mut url = reqwest::Url::parse(&Configuration::new().base_path).map_err(|e| MineError::parse("Modrinth API URL", e))?;
+    url.path_segments_mut().map_err(|_| MineError::Other("Modrinth API URL cannot have a path".to_string()))?
+        .extend(["project", name, "version"]);
+    url.query_pairs_mut().append_pair("loaders", &format!("[\"{}\"]", loader));
+    if let Some(game_version) = game_version {
+        url.query_pairs_mut().append_pair("game_versions", &format!("[\"{}\"]", game_version));
+    }
+
+    let body = cache::fetch(url.as_str()).await.map_err(|e| match e {
+        MineError::HttpStatus { status: 404, .. } => MineError::PluginNotFound { name: name.to_string(), reason: "no such Modrinth project".to_string() },
+        e => e,
+    })?;
+    let versions: Vec<Version> = serde_json::from_str(&body).map_err(|e| MineError::parse(format!("Modrinth project {}", name), e))?;
+
+    // versions are returned newest first
+    let version = versions.first().ok_or_else(|| MineError::PluginNotFound {
+        name: name.to_string(),
+        reason: format!("no {} version for Minecraft {}", loader, game_version.unwrap_or("(any)")),
+    })?;
+    let file = version.files.iter().find(|f| f.primary).or_else(|| version.files.first())
+        .ok_or_else(|| MineError::PluginNotFound { name: name.to_string(), reason: format!("version {} has no files", version.version_number) })?;
+
+    Ok(PluginFile {
+        url: file.url.clone(),
+        file_name: file.filename.clone(),
+        version: version.version_number.clone(),
+        checksum: file.hashes.sha1.clone().map(Checksum::Sha1),
+    })
+}
@@ -0,0 +1,218 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::cache;
+use crate::downloader::Checksum;
+use crate::error::{MineError, Result};
+use crate::manifest::Server;
+use crate::server::provider::{requirement, select, ResolvedServer, ServerArtifact, ServerProvider};
+use crate::server::vanilla::vanilla;
+use crate::version::{MinecraftVersion, VersionReq};
+
+// Arclight is only published as GitHub release assets
+const ARCLIGHT_RELEASES_URL: &str = "https://api.github.com/repos/IzzelAliz/Arclight/releases";
+
+// releases fetched per request, GitHub's maximum
+const PAGE_SIZE: usize = 100;
+
+// the mod loaders Arclight is built for, preferred in this order when none is asked for
+const PLATFORMS: &[&str] = &["forge", "neoforge", "fabric"];
+
+// https://api.github.com/repos/IzzelAliz/Arclight/releases
+#[derive(Deserialize)]
+struct GitHubRelease {
+    #[serde(default)]
+    prerelease: bool,
+    assets: Vec<GitHubAsset>,
+}
+
+#[derive(Deserialize)]
+struct GitHubAsset {
+    // arclight-forge-1.20.1-1.0.5.jar
+    name: String,
+    browser_download_url: String,
+    // "sha256:...", only on assets uploaded since GitHub started computing them
+    #[serde(default)]
+    digest: Option<String>,
+}
+
+// a server jar from a release
+struct ArclightBuild {
+    platform: String,
+    minecraft_version: String,
+    build: String,
+    prerelease: bool,
+    url: String,
+    sha256: Option<String>,
+}
+
+impl ArclightBuild {
+    fn parse(asset: GitHubAsset, prerelease: bool) -> Option<Self> {
+        let name = asset.name.strip_prefix("arclight-")?.strip_suffix(".jar")?;
+        let mut parts = name.splitn(3, '-');
+        let (platform, minecraft_version, build) = (parts.next()?, parts.next()?, parts.next()?);
+        Some(ArclightBuild {
+            platform: platform.to_string(),
+            minecraft_version: minecraft_version.to_string(),
+            build: build.to_string(),
+            prerelease,
+            url: asset.browser_download_url,
+            sha256: asset.digest.and_then(|digest| digest.strip_prefix("sha256:").map(str::to_string)),
+        })
+    }
+
+    // how a build is stored in mine.lock, e.g. forge-1.0.5
+    fn id(&self) -> String {
+        format!("{}-{}", self.platform, self.build)
+    }
+}
+
+// every server jar, newest release first
+async fn get_builds() -> Result<Vec<ArclightBuild>> {
+    let mut builds = Vec::new();
+    for page in 1.. {
+        let releases: Vec<GitHubRelease> = cache::fetch_json(&format!("{}?per_page={}&page={}", ARCLIGHT_RELEASES_URL, PAGE_SIZE, page)).await?;
+        let done = releases.len() < PAGE_SIZE;
+        for release in releases {
+            builds.extend(release.assets.into_iter().filter_map(|asset| ArclightBuild::parse(asset, release.prerelease)));
+        }
+        if done {
+            break;
+        }
+    }
+    Ok(builds)
+}
+
+// Minecraft versions with builds, newest first
+async fn minecraft_versions(builds: &[ArclightBuild]) -> Vec<String> {
+    let mut versions: Vec<MinecraftVersion> = Vec::new();
+    for build in builds {
+        if !versions.iter().any(|version| version.id == build.minecraft_version) {
+            versions.push(MinecraftVersion::parse(&build.minecraft_version));
+        }
+    }
+    vanilla::sort_newest_first(&mut versions).await;
+    versions.into_iter().map(|version| version.id).collect()
+}
+
+// Bukkit plugins running next to Forge, NeoForge or Fabric mods
+pub struct Arclight;
+
+#[async_trait]
+impl ServerProvider for Arclight {
+    fn kind(&self) -> &'static str {
+        "arclight"
+    }
+
+    fn name(&self) -> &'static str {
+        "Arclight"
+    }
+
+    // mods can be dropped into mods/ by hand
+    fn plugin_target(&self) -> Option<(&'static str, &'static str)> {
+        Some(("spigot", "plugins"))
+    }
+
+    async fn list_versions(&self, _snapshots: bool) -> Result<Vec<String>> {
+        Ok(minecraft_versions(&get_builds().await?).await)
+    }
+
+    // the builds of a Minecraft version for each platform, e.g. forge-1.0.5 or neoforge-1.0.0
+    async fn list_loaders(&self, version: &str, unstable: bool) -> Result<Vec<String>> {
+        let builds = get_builds().await?;
+        let version = match version {
+            "" => minecraft_versions(&builds).await.into_iter().next().unwrap_or_default(),
+            version => version.to_string(),
+        };
+        Ok(builds.iter()
+            .filter(|build| build.minecraft_version == version && (unstable || !build.prerelease))
+            .map(ArclightBuild::id)
+            .collect())
+    }
+
+    async fn resolve(&self, server: &Server) -> Result<ResolvedServer> {
+        let builds = get_builds().await?;
+        let minecraft_versions = minecraft_versions(&builds).await;
+        let minecraft_versions: Vec<&str> = minecraft_versions.iter().map(String::as_str).collect();
+
+        let req = requirement("version", &server.version)?;
+        let version = select(&req, &minecraft_versions, &minecraft_versions, minecraft_versions.first().copied(), |version, latest, suggestions| MineError::VersionNotFound { what: "Minecraft version", version, latest, suggestions })?;
+
+        // the platform asked for with loader, or the first one this version is built for
+        let builds: Vec<&ArclightBuild> = builds.iter().filter(|build| build.minecraft_version == version).collect();
+        let platforms: Vec<&str> = PLATFORMS.iter().copied().filter(|platform| builds.iter().any(|build| build.platform == *platform)).collect();
+        let platform = match server.loader.as_str() {
+            "" => platforms.first().copied().unwrap_or("forge"),
+            platform => platform,
+        };
+        if !platforms.contains(&platform) {
+            let suggestions = platforms.iter().map(|platform| platform.to_string()).collect();
+            return Err(MineError::LoaderNotFound { loader: format!("{} for {}", platform, version), latest: None, suggestions });
+        }
+
+        let builds: Vec<&ArclightBuild> = builds.into_iter().filter(|build| build.platform == platform).collect();
+        let listed: Vec<&str> = builds.iter().map(|build| build.build.as_str()).collect();
+        let stable: Vec<&str> = builds.iter().filter(|build| !build.prerelease).map(|build| build.build.as_str()).collect();
+
+        // pre-releases are only used when asked for, or when there is nothing else
+        let build_req = requirement("build", server.build.as_deref().unwrap_or_default())?;
+        let latest_build = match build_req {
+            VersionReq::LatestStable => stable.first(),
+            _ => stable.first().or(listed.first()),
+        };
+        let build = select(&build_req, &listed, &listed, latest_build.copied(), |build, latest, suggestions| MineError::BuildNotFound { version: version.clone(), build, latest, suggestions })?;
+
+        let mut resolved = ResolvedServer::new(self.kind(), version);
+        resolved.loader = Some(format!("{}-{}", platform, build));
+        Ok(resolved)
+    }
+
+    // the jar sets up its mod loader and libraries on first start
+    async fn artifacts(&self, resolved: &ResolvedServer) -> Result<Vec<ServerArtifact>> {
+        let id = resolved.loader.as_deref().unwrap_or_default();
+        let build = get_builds().await?.into_iter()
+            .find(|build| build.minecraft_version == resolved.version && build.id() == id)
+            .ok_or_else(|| MineError::BuildNotFound { version: resolved.version.clone(), build: id.to_string(), latest: None, suggestions: Vec::new() })?;
+
+        Ok(vec![ServerArtifact {
+            url: build.url,
+            path: "server.jar".to_string(),
+            checksum: build.sha256.map(Checksum::Sha256),
+        }])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(name: &str, digest: Option<&str>) -> GitHubAsset {
+        GitHubAsset {
+            name: name.to_string(),
+            browser_download_url: format!("https://github.com/IzzelAliz/Arclight/releases/download/1.0.5/{}", name),
+            digest: digest.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn parses_server_jars() {
+        let build = ArclightBuild::parse(asset("arclight-forge-1.20.1-1.0.5.jar", Some("sha256:abc123")), false).unwrap();
+        assert_eq!((build.platform.as_str(), build.minecraft_version.as_str(), build.build.as_str()), ("forge", "1.20.1", "1.0.5"));
+        assert_eq!(build.id(), "forge-1.0.5");
+        assert_eq!(build.sha256.as_deref(), Some("abc123"));
+        assert!(build.url.ends_with("/arclight-forge-1.20.1-1.0.5.jar"));
+
+        // the build keeps its dashes
+        let build = ArclightBuild::parse(asset("arclight-neoforge-1.21.1-1.0.2-SNAPSHOT.jar", Some("sha1:abc")), true).unwrap();
+        assert_eq!((build.platform.as_str(), build.minecraft_version.as_str(), build.build.as_str()), ("neoforge", "1.21.1", "1.0.2-SNAPSHOT"));
+        assert!(build.prerelease);
+        assert_eq!(build.sha256, None);
+    }
+
+    #[test]
+    fn ignores_other_assets() {
+        for name in ["arclight-forge-1.20.1-1.0.5-sources.txt", "Arclight-forge-1.20.1-1.0.5.jar", "arclight-forge-1.20.1.jar", "installer.jar"] {
+            assert!(ArclightBuild::parse(asset(name, None), false).is_none(), "{}", name);
+        }
+    }
+}
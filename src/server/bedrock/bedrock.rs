@@ -0,0 +1,256 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Component, Path};
+use zip::ZipArchive;
+
+use crate::cache;
+use crate::error::{MineError, Result};
+use crate::manifest::Server;
+use crate::server::provider::{requirement, Progress, ResolvedServer, ServerArtifact, ServerProvider};
+use crate::version::VersionReq;
+
+// the links behind the download buttons on minecraft.net, only ever for the current release and preview
+const BEDROCK_LINKS_URL: &str = "https://net-secondary.web.minecraft-services.net/api/v1.0/download/links";
+const BEDROCK_DOWNLOAD_URL: &str = "https://www.minecraft.net/bedrockdedicatedserver";
+
+const SERVER_ZIP: &str = "bedrock-server.zip";
+
+// the server's own configuration and data, which an upgrade must not overwrite
+const PRESERVED: &[&str] = &["server.properties", "permissions.json", "allowlist.json", "worlds"];
+
+// https://net-secondary.web.minecraft-services.net/api/v1.0/download/links
+#[derive(Deserialize)]
+struct DownloadLinks {
+    result: DownloadLinksResult,
+}
+
+#[derive(Deserialize)]
+struct DownloadLinksResult {
+    links: Vec<DownloadLink>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadLink {
+    // serverBedrockLinux, serverBedrockPreviewLinux, serverBedrockWindows, ...
+    download_type: String,
+    // .../bin-linux/bedrock-server-1.21.2.02.zip
+    download_url: String,
+}
+
+// the Linux server zip of a release, or of a preview
+fn download_url(version: &str, preview: bool) -> String {
+    let dir = if preview { "bin-linux-preview" } else { "bin-linux" };
+    format!("{}/{}/bedrock-server-{}.zip", BEDROCK_DOWNLOAD_URL, dir, version)
+}
+
+// the current release, or preview, from minecraft.net
+async fn get_latest(preview: bool) -> Result<String> {
+    let links: DownloadLinks = cache::fetch_json(BEDROCK_LINKS_URL).await?;
+    let download_type = if preview { "serverBedrockPreviewLinux" } else { "serverBedrockLinux" };
+    links.result.links.iter()
+        .find(|link| link.download_type == download_type)
+        .and_then(|link| link.download_url.rsplit('/').next()?.strip_prefix("bedrock-server-")?.strip_suffix(".zip"))
+        .map(str::to_string)
+        .ok_or_else(|| MineError::VersionNotFound { what: "Bedrock version", version: "latest".to_string(), latest: None, suggestions: Vec::new() })
+}
+
+// Bedrock versions have four numbers, e.g. 1.21.2.02
+fn is_version(version: &str) -> bool {
+    let numbers: Vec<&str> = version.split('.').collect();
+    numbers.len() == 4 && numbers.iter().all(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+// whether a zip entry is one of the preserved files, or inside a preserved directory, that dir already has
+fn is_preserved(name: &Path, dir: &Path) -> bool {
+    match name.components().next() {
+        Some(Component::Normal(first)) => PRESERVED.iter().any(|preserved| first == *preserved) && dir.join(first).exists(),
+        _ => false,
+    }
+}
+
+// extracts the server zip into dir, leaving the preserved files alone when they exist
+fn extract(zip: &Path, dir: &Path, progress: Progress<'_>) -> Result<()> {
+    let mut archive = ZipArchive::new(fs::File::open(zip)?).map_err(|e| MineError::parse(SERVER_ZIP, e))?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| MineError::parse(SERVER_ZIP, e))?;
+        // entries escaping the server directory are skipped
+        let Some(name) = entry.enclosed_name() else {
+            continue;
+        };
+        if is_preserved(&name, dir) {
+            continue;
+        }
+        let path = dir.join(&name);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        progress(&name.to_string_lossy());
+        io::copy(&mut entry, &mut fs::File::create(&path)?)?;
+
+        // keeps bedrock_server executable
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode))?;
+        }
+    }
+    Ok(())
+}
+
+pub struct Bedrock;
+
+#[async_trait]
+impl ServerProvider for Bedrock {
+    fn kind(&self) -> &'static str {
+        "bedrock"
+    }
+
+    fn name(&self) -> &'static str {
+        "Bedrock"
+    }
+
+    // the Bedrock server does not read eula.txt
+    fn needs_eula(&self) -> bool {
+        false
+    }
+
+    async fn list_versions(&self, snapshots: bool) -> Result<Vec<String>> {
+        let mut versions = Vec::new();
+        if snapshots {
+            versions.push(get_latest(true).await?);
+        }
+        versions.push(get_latest(false).await?);
+        Ok(versions)
+    }
+
+    // older versions are still downloadable but not listed, so exact versions are taken as they are
+    async fn resolve(&self, server: &Server) -> Result<ResolvedServer> {
+        let latest_version = get_latest(server.snapshot).await?;
+
+        let req = requirement("version", &server.version)?;
+        let version = match &req {
+            VersionReq::Latest | VersionReq::LatestStable => latest_version,
+            VersionReq::Exact(version) => match is_version(version) {
+                true => version.clone(),
+                false => return Err(MineError::VersionNotFound { what: "Bedrock version", version: version.clone(), latest: Some(latest_version), suggestions: Vec::new() }),
+            },
+            VersionReq::Constraints(_) => match req.matches(&latest_version) {
+                true => latest_version,
+                false => return Err(MineError::VersionNotFound { what: "Bedrock version", version: req.to_string(), latest: Some(latest_version), suggestions: Vec::new() }),
+            },
+        };
+
+        Ok(ResolvedServer::new(self.kind(), version))
+    }
+
+    async fn artifacts(&self, resolved: &ResolvedServer) -> Result<Vec<ServerArtifact>> {
+        // previews are only listed while they are current, so the link tells them apart
+        let preview = get_latest(true).await.is_ok_and(|preview| preview == resolved.version);
+
+        // minecraft.net publishes no checksum for the zips
+        Ok(vec![ServerArtifact {
+            url: download_url(&resolved.version, preview),
+            path: SERVER_ZIP.to_string(),
+            checksum: None,
+        }])
+    }
+
+    async fn install(&self, _server: &Server, _resolved: &ResolvedServer, dir: &Path, progress: Progress<'_>) -> Result<()> {
+        extract(&dir.join(SERVER_ZIP), dir, progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    #[test]
+    fn versions_have_four_numbers() {
+        assert!(is_version("1.21.2.02"));
+        assert!(is_version("1.20.80.05"));
+        for version in ["5", "1.21", "1.21.2", "1.21.2.02.1", "1.21..02", "1.21.2.x", "latest"] {
+            assert!(!is_version(version), "{}", version);
+        }
+    }
+
+    // a server zip with the files an upgrade has to keep, and the server itself
+    fn server_zip(path: &Path) {
+        let mut zip = ZipWriter::new(fs::File::create(path).unwrap());
+        let options = SimpleFileOptions::default();
+        for (name, content) in [
+            ("server.properties", "default properties"),
+            ("permissions.json", "[]"),
+            ("allowlist.json", "[]"),
+            ("behavior_packs/vanilla/manifest.json", "{}"),
+            ("../outside.txt", "escaped"),
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.add_directory("worlds/", options).unwrap();
+        zip.start_file("bedrock_server", options.unix_permissions(0o755)).unwrap();
+        zip.write_all(b"new server").unwrap();
+        zip.finish().unwrap();
+    }
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("mine-bedrock-test-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(dir.join("server")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn upgrades_keep_the_configuration_and_worlds() {
+        let dir = temp_dir("upgrade");
+        let server = dir.join("server");
+        server_zip(&dir.join(SERVER_ZIP));
+        fs::write(server.join("server.properties"), "level-name=mine").unwrap();
+        fs::write(server.join("permissions.json"), "[\"operator\"]").unwrap();
+        fs::write(server.join("allowlist.json"), "[\"player\"]").unwrap();
+        fs::create_dir_all(server.join("worlds/mine/db")).unwrap();
+        fs::write(server.join("worlds/mine/db/CURRENT"), "world").unwrap();
+        fs::write(server.join("bedrock_server"), "old server").unwrap();
+
+        extract(&dir.join(SERVER_ZIP), &server, &|_| {}).unwrap();
+
+        assert_eq!(fs::read_to_string(server.join("server.properties")).unwrap(), "level-name=mine");
+        assert_eq!(fs::read_to_string(server.join("permissions.json")).unwrap(), "[\"operator\"]");
+        assert_eq!(fs::read_to_string(server.join("allowlist.json")).unwrap(), "[\"player\"]");
+        assert_eq!(fs::read_to_string(server.join("worlds/mine/db/CURRENT")).unwrap(), "world");
+        assert_eq!(fs::read_to_string(server.join("bedrock_server")).unwrap(), "new server");
+        assert_eq!(fs::read_to_string(server.join("behavior_packs/vanilla/manifest.json")).unwrap(), "{}");
+        assert!(!dir.join("outside.txt").exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(server.join("bedrock_server")).unwrap().permissions().mode() & 0o777, 0o755);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn new_servers_get_the_default_configuration() {
+        let dir = temp_dir("new");
+        let server = dir.join("server");
+        server_zip(&dir.join(SERVER_ZIP));
+
+        extract(&dir.join(SERVER_ZIP), &server, &|_| {}).unwrap();
+
+        assert_eq!(fs::read_to_string(server.join("server.properties")).unwrap(), "default properties");
+        assert_eq!(fs::read_to_string(server.join("allowlist.json")).unwrap(), "[]");
+        assert!(server.join("worlds").is_dir());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,84 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::cache;
+use crate::error::{MineError, Result};
+use crate::manifest::Server;
+use crate::server::provider::{requirement, select, Progress, ResolvedServer, ServerArtifact, ServerProvider};
+use crate::server::proxy::{self, ProxyConfig};
+
+// BungeeCord has no releases, only the builds of md_5's Jenkins
+const BUNGEECORD_JENKINS_URL: &str = "https://ci.md-5.net/job/BungeeCord";
+
+// https://ci.md-5.net/job/BungeeCord/api/json?tree=allBuilds[number,result]
+#[derive(Deserialize)]
+struct JenkinsJob {
+    #[serde(rename = "allBuilds")]
+    all_builds: Vec<JenkinsBuild>,
+}
+
+#[derive(Deserialize)]
+struct JenkinsBuild {
+    number: u32,
+    // none while the build is running
+    result: Option<String>,
+}
+
+// successful builds, newest first; the others have no jar
+async fn get_builds() -> Result<Vec<String>> {
+    let job: JenkinsJob = cache::fetch_json(&format!("{}/api/json?tree=allBuilds[number,result]", BUNGEECORD_JENKINS_URL)).await?;
+    Ok(job.all_builds.into_iter()
+        .filter(|build| build.result.as_deref() == Some("SUCCESS"))
+        .map(|build| build.number.to_string())
+        .collect())
+}
+
+pub struct BungeeCord;
+
+#[async_trait]
+impl ServerProvider for BungeeCord {
+    fn kind(&self) -> &'static str {
+        "bungeecord"
+    }
+
+    fn name(&self) -> &'static str {
+        "BungeeCord"
+    }
+
+    fn needs_eula(&self) -> bool {
+        false
+    }
+
+    fn plugin_target(&self) -> Option<(&'static str, &'static str)> {
+        Some(("bungeecord", "plugins"))
+    }
+
+    // the versions are the Jenkins build numbers
+    async fn list_versions(&self, _snapshots: bool) -> Result<Vec<String>> {
+        get_builds().await
+    }
+
+    async fn resolve(&self, server: &Server) -> Result<ResolvedServer> {
+        let builds = get_builds().await?;
+        let builds: Vec<&str> = builds.iter().map(String::as_str).collect();
+
+        let req = requirement("version", &server.version)?;
+        let build = select(&req, &builds, &builds, builds.first().copied(), |version, latest, suggestions| MineError::VersionNotFound { what: "BungeeCord build", version, latest, suggestions })?;
+
+        Ok(ResolvedServer::new(self.kind(), build))
+    }
+
+    async fn artifacts(&self, resolved: &ResolvedServer) -> Result<Vec<ServerArtifact>> {
+        // Jenkins publishes no checksum for its artifacts
+        Ok(vec![ServerArtifact {
+            url: format!("{}/{}/artifact/bootstrap/target/BungeeCord.jar", BUNGEECORD_JENKINS_URL, resolved.version),
+            path: "server.jar".to_string(),
+            checksum: None,
+        }])
+    }
+
+    async fn install(&self, server: &Server, _resolved: &ResolvedServer, dir: &Path, _progress: Progress<'_>) -> Result<()> {
+        proxy::write_config(ProxyConfig::BungeeCord, dir, &server.backends)
+    }
+}
@@ -1,90 +1,69 @@
-use reqwest::get;
-use serde::Deserialize;
-use serde_json;
-use crate::version::Version;
+use async_trait::async_trait;
+use crate::error::Result;
+use crate::manifest::Server;
+use crate::server::fabric::meta;
+use crate::server::provider::{ResolvedServer, ServerArtifact, ServerProvider};
 
 const FABRIC_MANIFEST_URL: &str = "https://meta.fabricmc.net/v2/versions";
+// forks of Fabric's meta API for versions it does not cover: 1.13.2 and older, and beta 1.7.3
+const LEGACY_FABRIC_MANIFEST_URL: &str = "https://meta.legacyfabric.net/v2/versions";
+const BABRIC_MANIFEST_URL: &str = "https://meta.babric.glass-launcher.net/v2/versions";
 
-#[derive(Deserialize)]
-struct FabricManifest {
-    game: Vec<MinecraftVersion>,
-    loader: Vec<LoaderVersion>,
-    installer: Vec<InstallerVersion>,
+// Fabric, or a port of it to older versions, by the meta API it is resolved from
+pub struct Fabric {
+    // the type in mine.toml, also the Modrinth loader
+    kind: &'static str,
+    name: &'static str,
+    manifest_url: &'static str,
 }
 
-#[derive(Deserialize)]
-struct MinecraftVersion {
-    version: String,
-    stable: bool,
+impl Fabric {
+    pub const FABRIC: Fabric = Fabric { kind: "fabric", name: "Fabric", manifest_url: FABRIC_MANIFEST_URL };
+    pub const LEGACY_FABRIC: Fabric = Fabric { kind: "legacy-fabric", name: "Legacy Fabric", manifest_url: LEGACY_FABRIC_MANIFEST_URL };
+    pub const BABRIC: Fabric = Fabric { kind: "babric", name: "Babric", manifest_url: BABRIC_MANIFEST_URL };
 }
 
-#[derive(Deserialize)]
-struct LoaderVersion {
-    version: String,
-    stable: bool,
-}
-
-#[derive(Deserialize)]
-struct InstallerVersion {
-    version: String,
-    stable: bool,
-}
-
-async fn get_fabric_manifest() -> Result<FabricManifest, Box<dyn std::error::Error>> {
-    let response = get(FABRIC_MANIFEST_URL).await?.text().await?;
-    let manifest: FabricManifest = serde_json::from_str(&response)?;
-    Ok(manifest)
-}
-
-async fn get_latest_minecraft_version() -> Result<String, Box<dyn std::error::Error>> {
-    let manifest = get_fabric_manifest().await?;
-    let latest_minecraft_version = manifest.game.iter().find(|v| v.stable).unwrap();
-    Ok(latest_minecraft_version.version.clone())
-}
-
-async fn get_latest_loader_version(unstable: bool) -> Result<String, Box<dyn std::error::Error>> {
-    let manifest = get_fabric_manifest().await?;
-    let latest_loader_version = manifest.loader.iter().find(|v| unstable || v.stable).unwrap();
-    Ok(latest_loader_version.version.clone())
-}
+#[async_trait]
+impl ServerProvider for Fabric {
+    fn kind(&self) -> &'static str {
+        self.kind
+    }
 
-async fn get_latest_installer_version(unstable: bool) -> Result<String, Box<dyn std::error::Error>> {
-    let manifest = get_fabric_manifest().await?;
-    let latest_installer_version = manifest.installer.iter().find(|v| unstable || v.stable).unwrap();
-    Ok(latest_installer_version.version.clone())
-}
+    fn name(&self) -> &'static str {
+        self.name
+    }
 
-pub async fn get_download_link(version: Option<String>, loader_version: Option<String>, installer_version: Option<String>, unstable_loader: bool, unstable_installer: bool) -> Result<(String, String), Box<dyn std::error::Error>> {
-    let latest_minecraft_version = get_latest_minecraft_version().await?;
-    let latest_loader_version = get_latest_loader_version(unstable_loader).await?;
-    let latest_installer_version = get_latest_installer_version(unstable_installer).await?;
+    fn plugin_target(&self) -> Option<(&'static str, &'static str)> {
+        Some((self.kind, "mods"))
+    }
 
-    let version = match version {
-        Some(ref v) if !v.is_empty() => Some(v.clone()),
-        _ => Some(latest_minecraft_version.clone())
-    };
-    let loader_version = match loader_version {
-        Some(ref v) if !v.is_empty() => Some(v.clone()),
-        _ => Some(latest_loader_version.clone())
-    };
-    let installer_version = match installer_version {
-        Some(ref v) if !v.is_empty() => Some(v.clone()),
-        _ => Some(latest_installer_version.clone())
-    };
+    async fn list_versions(&self, snapshots: bool) -> Result<Vec<String>> {
+        let manifest = meta::get_manifest(self.manifest_url).await?;
+        Ok(meta::list(manifest.game, snapshots))
+    }
 
-    if Version::from_str(version.clone().unwrap().as_str()) > Version::from_str(latest_minecraft_version.as_str()) {
-        return Err(format!("Minecraft version {} not found. Latest is {}", version.clone().unwrap(), latest_minecraft_version).into());
+    async fn list_loaders(&self, _version: &str, unstable: bool) -> Result<Vec<String>> {
+        let manifest = meta::get_manifest(self.manifest_url).await?;
+        Ok(meta::list(manifest.loader, unstable))
     }
 
-    if Version::from_str(loader_version.clone().unwrap().as_str()) > Version::from_str(latest_loader_version.as_str()) {
-        return Err(format!("Loader version {} not found. Latest is {}", loader_version.clone().unwrap(), latest_loader_version).into());
+    async fn list_installers(&self, unstable: bool) -> Result<Vec<String>> {
+        let manifest = meta::get_manifest(self.manifest_url).await?;
+        Ok(meta::list(manifest.installer, unstable))
     }
 
-    if Version::from_str(installer_version.clone().unwrap().as_str()) > Version::from_str(latest_installer_version.as_str()) {
-        return Err(format!("Installer version {} not found. Latest is {}", installer_version.clone().unwrap(), latest_installer_version).into());
+    async fn resolve(&self, server: &Server) -> Result<ResolvedServer> {
+        meta::resolve(self.kind(), self.manifest_url, server).await
     }
 
-    let url = format!("https://meta.fabricmc.net/v2/versions/loader/{}/{}/{}/server/jar", version.clone().unwrap(), loader_version.clone().unwrap(), installer_version.clone().unwrap());
-    let version_info = format!("(Version: {}, Loader: {}, Installer: {})", version.unwrap(), loader_version.unwrap(), installer_version.unwrap());
-    Ok((url, version_info))
+    async fn artifacts(&self, resolved: &ResolvedServer) -> Result<Vec<ServerArtifact>> {
+        let (loader, installer) = meta::loader_and_installer(resolved)?;
+
+        // the server launcher jar is generated on the fly, so the meta API publishes no checksum for it
+        Ok(vec![ServerArtifact {
+            url: format!("{}/loader/{}/{}/{}/server/jar", self.manifest_url, resolved.version, loader, installer),
+            path: "server.jar".to_string(),
+            checksum: None,
+        }])
+    }
 }
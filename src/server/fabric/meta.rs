@@ -0,0 +1,123 @@
+// Fabric's meta API, which Quilt's, Legacy Fabric's and Babric's mirror: game, loader and installer versions, newest first
+
+use serde::Deserialize;
+
+use crate::cache;
+use crate::error::{MineError, Result};
+use crate::manifest::Server;
+use crate::server::provider::{requirement, ResolvedServer};
+use crate::version::{nearest, VersionReq};
+
+// e.g. https://meta.fabricmc.net/v2/versions
+#[derive(Deserialize)]
+pub struct MetaManifest {
+    pub game: Vec<MetaVersion>,
+    pub loader: Vec<MetaVersion>,
+    pub installer: Vec<MetaVersion>,
+}
+
+#[derive(Deserialize)]
+pub struct MetaVersion {
+    pub version: String,
+    // Quilt only marks game versions, its loader and installer betas are suffixed instead (0.26.0-beta.1)
+    #[serde(default)]
+    stable: Option<bool>,
+}
+
+impl MetaVersion {
+    pub fn is_stable(&self) -> bool {
+        self.stable.unwrap_or_else(|| !self.version.contains('-'))
+    }
+}
+
+pub async fn get_manifest(url: &str) -> Result<MetaManifest> {
+    cache::fetch_json(url).await
+}
+
+// the listed versions, skipping unstable ones unless asked for
+pub fn list(versions: Vec<MetaVersion>, unstable: bool) -> Vec<String> {
+    versions.into_iter()
+        .filter(|v| unstable || v.is_stable())
+        .map(|v| v.version)
+        .collect()
+}
+
+fn pairs(versions: &[MetaVersion]) -> Vec<(&str, bool)> {
+    versions.iter().map(|v| (v.version.as_str(), v.is_stable())).collect()
+}
+
+// resolves a requirement against (version, stable) pairs listed newest first; `latest` also considers unstable versions when allowed
+fn select(req: &VersionReq, versions: &[(&str, bool)], unstable: bool) -> Option<String> {
+    let mut candidates = versions.iter().filter(|(_, stable)| unstable || *stable).map(|(version, _)| *version);
+    match req {
+        VersionReq::Latest => candidates.next().map(str::to_string),
+        VersionReq::LatestStable => versions.iter().find(|(_, stable)| *stable).map(|(version, _)| version.to_string()),
+        VersionReq::Exact(version) => Some(version.clone()),
+        VersionReq::Constraints(_) => req.select(candidates).map(str::to_string),
+    }
+}
+
+// the newest version, skipping unstable ones unless allowed
+fn latest(versions: &[(&str, bool)], unstable: bool) -> Option<String> {
+    select(&VersionReq::Latest, versions, unstable)
+}
+
+// the closest listed versions when a version is not listed at all
+fn missing(version: &str, versions: &[(&str, bool)]) -> Option<Vec<String>> {
+    if versions.iter().any(|(listed, _)| *listed == version) {
+        return None;
+    }
+    let listed: Vec<&str> = versions.iter().map(|(listed, _)| *listed).collect();
+    Some(nearest(version, &listed))
+}
+
+// resolves the game, loader and installer versions of a server
+pub async fn resolve(kind: &str, url: &str, server: &Server) -> Result<ResolvedServer> {
+    let manifest = get_manifest(url).await?;
+    let game = pairs(&manifest.game);
+    let loaders = pairs(&manifest.loader);
+    let installers = pairs(&manifest.installer);
+
+    let latest_minecraft_version = latest(&game, false)
+        .ok_or_else(|| MineError::VersionNotFound { what: "Minecraft version", version: "latest".to_string(), latest: None, suggestions: Vec::new() })?;
+    let latest_loader_version = latest(&loaders, server.unstable_loader)
+        .ok_or_else(|| MineError::LoaderNotFound { loader: "latest".to_string(), latest: None, suggestions: Vec::new() })?;
+    let latest_installer_version = latest(&installers, server.unstable_installer)
+        .ok_or_else(|| MineError::InstallerNotFound { installer: "latest".to_string(), latest: None, suggestions: Vec::new() })?;
+
+    let req = requirement("version", &server.version)?;
+    let version = select(&req, &game, server.snapshot)
+        .ok_or_else(|| MineError::VersionNotFound { what: "Minecraft version", version: req.to_string(), latest: Some(latest_minecraft_version.clone()), suggestions: Vec::new() })?;
+
+    let req = requirement("loader", &server.loader)?;
+    let loader_version = select(&req, &loaders, server.unstable_loader)
+        .ok_or_else(|| MineError::LoaderNotFound { loader: req.to_string(), latest: Some(latest_loader_version.clone()), suggestions: Vec::new() })?;
+
+    let req = requirement("installer", &server.installer)?;
+    let installer_version = select(&req, &installers, server.unstable_installer)
+        .ok_or_else(|| MineError::InstallerNotFound { installer: req.to_string(), latest: Some(latest_installer_version.clone()), suggestions: Vec::new() })?;
+
+    if let Some(suggestions) = missing(&version, &game) {
+        return Err(MineError::VersionNotFound { what: "Minecraft version", version, latest: Some(latest_minecraft_version), suggestions });
+    }
+
+    if let Some(suggestions) = missing(&loader_version, &loaders) {
+        return Err(MineError::LoaderNotFound { loader: loader_version, latest: Some(latest_loader_version), suggestions });
+    }
+
+    if let Some(suggestions) = missing(&installer_version, &installers) {
+        return Err(MineError::InstallerNotFound { installer: installer_version, latest: Some(latest_installer_version), suggestions });
+    }
+
+    let mut resolved = ResolvedServer::new(kind, version);
+    resolved.loader = Some(loader_version);
+    resolved.installer = Some(installer_version);
+    Ok(resolved)
+}
+
+// the loader and installer versions of a resolved server
+pub fn loader_and_installer(resolved: &ResolvedServer) -> Result<(&str, &str)> {
+    let loader = resolved.loader.as_ref().ok_or_else(|| MineError::LoaderNotFound { loader: "none".to_string(), latest: None, suggestions: Vec::new() })?;
+    let installer = resolved.installer.as_ref().ok_or_else(|| MineError::InstallerNotFound { installer: "none".to_string(), latest: None, suggestions: Vec::new() })?;
+    Ok((loader, installer))
+}
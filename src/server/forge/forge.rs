@@ -0,0 +1,148 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::cache;
+use crate::error::{MineError, Result};
+use crate::manifest::Server;
+use crate::server::{java, maven};
+use crate::server::provider::{requirement, select, Progress, ResolvedServer, ServerArtifact, ServerProvider};
+use crate::server::vanilla::vanilla;
+use crate::version::{MinecraftVersion, VersionReq};
+
+const FORGE_PROMOTIONS_URL: &str = "https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json";
+const FORGE_MAVEN_URL: &str = "https://maven.minecraftforge.net/net/minecraftforge/forge";
+
+const INSTALLER_JAR: &str = "forge-installer.jar";
+
+// https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json
+#[derive(Deserialize)]
+struct ForgePromotions {
+    promos: HashMap<String, String>, // "1.20.1-latest": "47.3.0", "1.20.1-recommended": "47.2.0"
+}
+
+// https://maven.minecraftforge.net/net/minecraftforge/forge/maven-metadata.json
+// maps Minecraft versions to maven versions, oldest first: "1.20.1": ["1.20.1-46.0.1", ...]
+// (legacy ones carry a suffix, e.g. "1.7.10-10.13.4.1614-1.7.10")
+type ForgeVersions = HashMap<String, Vec<String>>;
+
+async fn get_promotions() -> Result<ForgePromotions> {
+    cache::fetch_json(FORGE_PROMOTIONS_URL).await
+}
+
+async fn get_versions() -> Result<ForgeVersions> {
+    cache::fetch_json(&format!("{}/maven-metadata.json", FORGE_MAVEN_URL)).await
+}
+
+// Minecraft versions with Forge builds, newest first
+async fn minecraft_versions(versions: &ForgeVersions, snapshots: bool) -> Vec<String> {
+    let mut minecraft_versions: Vec<MinecraftVersion> = versions.keys()
+        .map(|id| MinecraftVersion::parse(id))
+        .filter(|version| snapshots || version.is_release())
+        .collect();
+    vanilla::sort_newest_first(&mut minecraft_versions).await;
+    minecraft_versions.into_iter().map(|version| version.id).collect()
+}
+
+// Forge versions of a Minecraft version without the Minecraft version prefix, newest first
+fn forge_versions<'a>(versions: &'a ForgeVersions, version: &str) -> Vec<&'a str> {
+    let prefix = format!("{}-", version);
+    versions.get(version).into_iter().flatten().rev()
+        .filter_map(|maven_version| maven_version.strip_prefix(&prefix))
+        .collect()
+}
+
+// a listed Forge version, also accepting legacy ones without their suffix ("10.13.4.1614" for "10.13.4.1614-1.7.10")
+fn find<'a>(forge_versions: &[&'a str], forge_version: &str) -> Option<&'a str> {
+    forge_versions.iter().copied()
+        .find(|listed| *listed == forge_version || listed.starts_with(&format!("{}-", forge_version)))
+}
+
+// the promoted ("latest" or "recommended") Forge version of a Minecraft version
+fn promoted<'a>(promotions: &ForgePromotions, version: &str, promotion: &str, forge_versions: &[&'a str]) -> Option<&'a str> {
+    find(forge_versions, promotions.promos.get(&format!("{}-{}", version, promotion))?)
+}
+
+pub struct Forge;
+
+#[async_trait]
+impl ServerProvider for Forge {
+    fn kind(&self) -> &'static str {
+        "forge"
+    }
+
+    fn name(&self) -> &'static str {
+        "Forge"
+    }
+
+    fn plugin_target(&self) -> Option<(&'static str, &'static str)> {
+        Some(("forge", "mods"))
+    }
+
+    async fn list_versions(&self, snapshots: bool) -> Result<Vec<String>> {
+        let versions = get_versions().await?;
+        Ok(minecraft_versions(&versions, snapshots).await)
+    }
+
+    async fn list_loaders(&self, version: &str, _unstable: bool) -> Result<Vec<String>> {
+        let versions = get_versions().await?;
+        let version = match version {
+            "" => minecraft_versions(&versions, false).await.into_iter().next().unwrap_or_default(),
+            version => version.to_string(),
+        };
+        Ok(forge_versions(&versions, &version).into_iter().map(str::to_string).collect())
+    }
+
+    async fn resolve(&self, server: &Server) -> Result<ResolvedServer> {
+        let versions = get_versions().await?;
+        let promotions = get_promotions().await?;
+
+        let candidates = minecraft_versions(&versions, server.snapshot).await;
+        let candidates: Vec<&str> = candidates.iter().map(String::as_str).collect();
+        let listed: Vec<&str> = versions.keys().map(String::as_str).filter(|version| !forge_versions(&versions, version).is_empty()).collect();
+
+        let req = requirement("version", &server.version)?;
+        let version = select(&req, &candidates, &listed, candidates.first().copied(), |version, latest, suggestions| MineError::VersionNotFound { what: "Minecraft version", version, latest, suggestions })?;
+
+        let forge_versions = forge_versions(&versions, &version);
+        // a listed Minecraft version has Forge versions, unless upstream's lists disagree
+        let latest_forge_version = promoted(&promotions, &version, "latest", &forge_versions)
+            .or(forge_versions.first().copied())
+            .ok_or_else(|| MineError::VersionNotFound { what: "Minecraft version", version: version.clone(), latest: candidates.first().map(|v| v.to_string()), suggestions: Vec::new() })?;
+
+        let req = match requirement("forge", &server.loader)? {
+            VersionReq::Exact(forge_version) => VersionReq::Exact(find(&forge_versions, &forge_version).unwrap_or(&forge_version).to_string()),
+            req => req,
+        };
+        let latest = match req {
+            VersionReq::LatestStable => promoted(&promotions, &version, "recommended", &forge_versions)
+                .ok_or_else(|| MineError::LoaderNotFound { loader: format!("recommended for {}", version), latest: Some(latest_forge_version.to_string()), suggestions: Vec::new() })?,
+            _ => latest_forge_version,
+        };
+        let forge_version = select(&req, &forge_versions, &forge_versions, Some(latest), |loader, latest, suggestions| MineError::LoaderNotFound { loader, latest, suggestions })?;
+
+        let mut resolved = ResolvedServer::new(self.kind(), version);
+        resolved.loader = Some(forge_version);
+        Ok(resolved)
+    }
+
+    async fn artifacts(&self, resolved: &ResolvedServer) -> Result<Vec<ServerArtifact>> {
+        let forge_version = resolved.loader.as_ref().ok_or_else(|| MineError::LoaderNotFound { loader: "none".to_string(), latest: None, suggestions: Vec::new() })?;
+        let maven_version = format!("{}-{}", resolved.version, forge_version);
+        let url = format!("{}/{}/forge-{}-installer.jar", FORGE_MAVEN_URL, maven_version, maven_version);
+
+        let checksum = maven::sha1(&url).await;
+
+        Ok(vec![ServerArtifact {
+            url,
+            path: INSTALLER_JAR.to_string(),
+            checksum,
+        }])
+    }
+
+    // the installer sets up run.sh and libraries/ for modern versions and the universal jar for legacy ones
+    async fn install(&self, _server: &Server, _resolved: &ResolvedServer, dir: &Path, progress: Progress<'_>) -> Result<()> {
+        java::run_jar(dir, INSTALLER_JAR, &["--installServer"], progress).await
+    }
+}
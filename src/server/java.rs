@@ -0,0 +1,146 @@
+use std::fs;
+use std::io::Write;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+
+use crate::error::{MineError, Result};
+use crate::server::provider::Progress;
+
+// where Java is usually installed on Linux, macOS and by SDKMAN!, one installation per subdirectory
+const JAVA_DIRS: &[&str] = &["/usr/lib/jvm", "/usr/java", "/opt/java", "/Library/Java/JavaVirtualMachines"];
+
+fn java_bin(home: &Path) -> PathBuf {
+    home.join("bin").join(format!("java{}", std::env::consts::EXE_SUFFIX))
+}
+
+// $JAVA_HOME/bin/java, or java from the PATH
+fn java() -> PathBuf {
+    match std::env::var_os("JAVA_HOME") {
+        Some(home) => java_bin(Path::new(&home)),
+        None => PathBuf::from("java"),
+    }
+}
+
+// java from JAVA_HOME, the PATH and then the usual install locations, newest first
+fn candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Some(home) = std::env::var_os("JAVA_HOME") {
+        candidates.push(java_bin(Path::new(&home)));
+    }
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    candidates.extend(std::env::split_paths(&path).map(|dir| dir.join(format!("java{}", std::env::consts::EXE_SUFFIX))));
+
+    let mut dirs: Vec<PathBuf> = JAVA_DIRS.iter().map(PathBuf::from).collect();
+    dirs.extend(dirs::home_dir().map(|home| home.join(".sdkman").join("candidates").join("java")));
+    if let Some(program_files) = std::env::var_os("ProgramFiles") {
+        dirs.extend(["Java", "Eclipse Adoptium", "Microsoft"].iter().map(|vendor| Path::new(&program_files).join(vendor)));
+    }
+
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut homes: Vec<PathBuf> = entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect();
+        homes.sort_by(|a, b| b.cmp(a));
+        for home in homes {
+            // macOS bundles keep the installation in Contents/Home
+            candidates.push(java_bin(&home));
+            candidates.push(java_bin(&home.join("Contents").join("Home")));
+        }
+    }
+
+    // the same installation is often linked from several places, e.g. /usr/bin/java
+    let mut seen = Vec::new();
+    candidates.retain(|java| match fs::canonicalize(java) {
+        Ok(target) if !seen.contains(&target) => {
+            seen.push(target);
+            true
+        },
+        _ => false,
+    });
+    candidates
+}
+
+// the major version from `java -version`, e.g. 8 for "1.8.0_392" or 21 for "21.0.2"
+async fn major_version(java: &Path) -> Option<u32> {
+    let output = Command::new(java).arg("-version").stdin(Stdio::null()).output().await.ok()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let version = stderr.split('"').nth(1)?;
+    let mut numbers = version.split(|c: char| !c.is_ascii_digit());
+    match numbers.next()?.parse().ok()? {
+        1 => numbers.next()?.parse().ok(),
+        major => Some(major),
+    }
+}
+
+// the first Java installation with one of the given major versions
+pub async fn find(versions: RangeInclusive<u32>) -> Result<PathBuf> {
+    let mut found = Vec::new();
+    for java in candidates() {
+        if let Some(major) = major_version(&java).await {
+            if versions.contains(&major) {
+                return Ok(java);
+            }
+            found.push(format!("Java {} at {}", major, java.display()));
+        }
+    }
+
+    let required = match (versions.start(), versions.end()) {
+        (start, end) if start == end => format!("Java {}", start),
+        (start, end) => format!("Java {} to {}", start, end),
+    };
+    let found = match found.is_empty() {
+        true => "none was found".to_string(),
+        false => format!("only found {}", found.join(", ")),
+    };
+    Err(MineError::Other(format!("{} is required but {}. Set JAVA_HOME to pick an installation", required, found)))
+}
+
+// runs `java -jar <jar> <args>` in dir with $JAVA_HOME/bin/java or java from the PATH
+pub async fn run_jar(dir: &Path, jar: &str, args: &[&str], progress: Progress<'_>) -> Result<()> {
+    run_jar_with(&java(), dir, jar, args, progress).await
+}
+
+// runs `java -jar <jar> <args>` in dir without a terminal, passing each line it prints to progress
+// and writing them all to <jar>.log
+pub async fn run_jar_with(java: &Path, dir: &Path, jar: &str, args: &[&str], progress: Progress<'_>) -> Result<()> {
+    let mut child = Command::new(java)
+        .arg("-jar")
+        .arg(jar)
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| MineError::Other(format!("Failed to run {} (Java is required to run {}): {}", java.display(), jar, e)))?;
+
+    let log = dir.join(format!("{}.log", jar));
+    let mut log_file = fs::File::create(&log)?;
+
+    // both are piped above
+    let mut stdout = BufReader::new(child.stdout.take().expect("piped stdout")).split(b'\n');
+    let mut stderr = BufReader::new(child.stderr.take().expect("piped stderr")).split(b'\n');
+    let (mut stdout_open, mut stderr_open) = (true, true);
+    while stdout_open || stderr_open {
+        let line = tokio::select! {
+            line = stdout.next_segment(), if stdout_open => line?.or_else(|| { stdout_open = false; None }),
+            line = stderr.next_segment(), if stderr_open => line?.or_else(|| { stderr_open = false; None }),
+        };
+        if let Some(line) = line {
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches('\r');
+            writeln!(log_file, "{}", line)?;
+            progress(line);
+        }
+    }
+
+    let status = child.wait().await?;
+    if !status.success() {
+        return Err(MineError::Other(format!("{} failed ({}), see {}", jar, status, log.display())));
+    }
+    Ok(())
+}
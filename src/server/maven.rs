@@ -0,0 +1,21 @@
+// Maven repositories, where Forge, NeoForge and others publish their installers
+
+use crate::cache;
+use crate::downloader::Checksum;
+use crate::error::Result;
+
+// the <version> entries of an artifact's maven-metadata.xml, as listed (oldest first)
+pub async fn versions(artifact_url: &str) -> Result<Vec<String>> {
+    let metadata = cache::fetch(&format!("{}/maven-metadata.xml", artifact_url)).await?;
+    Ok(metadata.split("<version>")
+        .skip(1)
+        .filter_map(|rest| rest.split_once("</version>"))
+        .map(|(version, _)| version.trim().to_string())
+        .collect())
+}
+
+// the .sha1 maven publishes next to every file, when it can be fetched
+pub async fn sha1(url: &str) -> Option<Checksum> {
+    let sha1 = cache::fetch(&format!("{}.sha1", url)).await.ok()?;
+    Some(Checksum::Sha1(sha1.trim().to_string()))
+}
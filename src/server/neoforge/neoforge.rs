@@ -0,0 +1,191 @@
+use async_trait::async_trait;
+use std::cmp::Ordering;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::{MineError, Result};
+use crate::manifest::Server;
+use crate::server::provider::{requirement, select, Progress, ResolvedServer, ServerArtifact, ServerProvider};
+use crate::server::{java, maven};
+use crate::version::{Version, VersionReq};
+use crate::warning;
+
+const NEOFORGE_MAVEN_URL: &str = "https://maven.neoforged.net/releases/net/neoforged/neoforge";
+
+const INSTALLER_JAR: &str = "neoforge-installer.jar";
+
+// a NeoForge version and the Minecraft version it is built for
+struct NeoForgeVersion {
+    version: String,
+    minecraft_version: String,
+}
+
+impl NeoForgeVersion {
+    // NeoForge versions start with the Minecraft version they are built for. Up to 1.21.x it is
+    // without the leading "1.": 20.4.237 is for 1.20.4 and 21.0.167 for 1.21. From 26.1 on it is
+    // the whole version followed by the build: 26.1.0.5 is for 26.1 and 26.1.1.2 for 26.1.1.
+    // Snapshot builds have a 0 and the snapshot first, e.g. 0.25w14craftmine.3-beta.
+    // Betas are suffixed, e.g. 20.2.3-beta.
+    fn parse(version: &str) -> Option<Self> {
+        let base = version.split('-').next()?;
+        let minecraft_version = match base.split('.').collect::<Vec<_>>()[..] {
+            ["0", snapshot, build] if !snapshot.is_empty() && build.parse::<u32>().is_ok() => snapshot.to_string(),
+            _ => {
+                let numbers: Vec<u32> = base.split('.').map(|n| n.parse().ok()).collect::<Option<_>>()?;
+                match numbers[..] {
+                    [major, 0, _] => format!("1.{}", major),
+                    [major, minor, _] => format!("1.{}.{}", major, minor),
+                    [year, drop, 0, _] => format!("{}.{}", year, drop),
+                    [year, drop, hotfix, _] => format!("{}.{}.{}", year, drop, hotfix),
+                    _ => return None,
+                }
+            },
+        };
+        Some(NeoForgeVersion { version: version.to_string(), minecraft_version })
+    }
+
+    fn is_stable(&self) -> bool {
+        !self.version.contains('-')
+    }
+
+    fn numbers(&self) -> Option<Version> {
+        Version::from_str(self.version.split('-').next()?).ok()
+    }
+}
+
+// every NeoForge version, newest first
+async fn get_versions() -> Result<Vec<NeoForgeVersion>> {
+    let mut versions = Vec::new();
+    let mut unknown = Vec::new();
+    for version in maven::versions(NEOFORGE_MAVEN_URL).await? {
+        match NeoForgeVersion::parse(&version) {
+            Some(version) => versions.push(version),
+            None => unknown.push(version),
+        }
+    }
+    if !unknown.is_empty() {
+        warning::warn(&format!("Ignoring NeoForge versions for an unknown Minecraft version: {}", unknown.join(", ")));
+    }
+    versions.sort_by(|a, b| b.numbers().partial_cmp(&a.numbers()).unwrap_or(Ordering::Equal));
+    Ok(versions)
+}
+
+// Minecraft versions with NeoForge versions, newest first
+fn minecraft_versions(versions: &[NeoForgeVersion]) -> Vec<&str> {
+    let mut minecraft_versions: Vec<&str> = Vec::new();
+    for version in versions {
+        if !minecraft_versions.contains(&version.minecraft_version.as_str()) {
+            minecraft_versions.push(&version.minecraft_version);
+        }
+    }
+    minecraft_versions
+}
+
+pub struct NeoForge;
+
+#[async_trait]
+impl ServerProvider for NeoForge {
+    fn kind(&self) -> &'static str {
+        "neoforge"
+    }
+
+    fn name(&self) -> &'static str {
+        "NeoForge"
+    }
+
+    fn plugin_target(&self) -> Option<(&'static str, &'static str)> {
+        Some(("neoforge", "mods"))
+    }
+
+    async fn list_versions(&self, snapshots: bool) -> Result<Vec<String>> {
+        let versions: Vec<NeoForgeVersion> = get_versions().await?.into_iter().filter(|v| snapshots || v.is_stable()).collect();
+        Ok(minecraft_versions(&versions).into_iter().map(str::to_string).collect())
+    }
+
+    async fn list_loaders(&self, version: &str, unstable: bool) -> Result<Vec<String>> {
+        let versions: Vec<NeoForgeVersion> = get_versions().await?.into_iter().filter(|v| unstable || v.is_stable()).collect();
+        let version = match version {
+            "" => minecraft_versions(&versions).first().map(|v| v.to_string()).unwrap_or_default(),
+            version => version.to_string(),
+        };
+        Ok(versions.into_iter().filter(|v| v.minecraft_version == version).map(|v| v.version).collect())
+    }
+
+    async fn resolve(&self, server: &Server) -> Result<ResolvedServer> {
+        let versions = get_versions().await?;
+        let candidates: Vec<&NeoForgeVersion> = versions.iter().filter(|v| server.unstable_loader || v.is_stable()).collect();
+
+        let all_minecraft_versions = minecraft_versions(&versions);
+        let candidate_minecraft_versions: Vec<&str> = candidates.iter().map(|v| v.minecraft_version.as_str()).collect();
+
+        let req = requirement("version", &server.version)?;
+        let version = select(&req, &candidate_minecraft_versions, &all_minecraft_versions, candidate_minecraft_versions.first().copied(), |version, latest, suggestions| MineError::VersionNotFound { what: "Minecraft version", version, latest, suggestions })?;
+
+        // every NeoForge version of this Minecraft version, and those that may be picked
+        let listed: Vec<&str> = versions.iter().filter(|v| v.minecraft_version == version).map(|v| v.version.as_str()).collect();
+        let candidates: Vec<&str> = candidates.iter().filter(|v| v.minecraft_version == version).map(|v| v.version.as_str()).collect();
+
+        let req = requirement("neoforge", &server.loader)?;
+        let latest = match req {
+            VersionReq::LatestStable => listed.iter().find(|v| !v.contains('-')),
+            _ => candidates.first(),
+        };
+        let neoforge_version = select(&req, &candidates, &listed, latest.copied(), |loader, latest, suggestions| MineError::LoaderNotFound {
+            loader: format!("{} for {}", loader, version),
+            // nothing is picked when this Minecraft version only has betas, which need --unstable-loader
+            suggestions: match suggestions.is_empty() {
+                true => listed.first().map(|v| v.to_string()).into_iter().collect(),
+                false => suggestions,
+            },
+            latest,
+        })?;
+
+        let mut resolved = ResolvedServer::new(self.kind(), version);
+        resolved.loader = Some(neoforge_version);
+        Ok(resolved)
+    }
+
+    async fn artifacts(&self, resolved: &ResolvedServer) -> Result<Vec<ServerArtifact>> {
+        let neoforge_version = resolved.loader.as_ref().ok_or_else(|| MineError::LoaderNotFound { loader: "none".to_string(), latest: None, suggestions: Vec::new() })?;
+        let url = format!("{}/{}/neoforge-{}-installer.jar", NEOFORGE_MAVEN_URL, neoforge_version, neoforge_version);
+        let checksum = maven::sha1(&url).await;
+
+        Ok(vec![ServerArtifact {
+            url,
+            path: INSTALLER_JAR.to_string(),
+            checksum,
+        }])
+    }
+
+    // like Forge's, the installer sets up run.sh and libraries/
+    async fn install(&self, _server: &Server, _resolved: &ResolvedServer, dir: &Path, progress: Progress<'_>) -> Result<()> {
+        java::run_jar(dir, INSTALLER_JAR, &["--installServer"], progress).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minecraft_version(version: &str) -> Option<String> {
+        NeoForgeVersion::parse(version).map(|v| v.minecraft_version)
+    }
+
+    #[test]
+    fn parses_the_minecraft_version() {
+        assert_eq!(minecraft_version("20.4.237").as_deref(), Some("1.20.4"));
+        assert_eq!(minecraft_version("21.0.167").as_deref(), Some("1.21"));
+        assert_eq!(minecraft_version("20.2.3-beta").as_deref(), Some("1.20.2"));
+        assert_eq!(minecraft_version("26.1.0.5").as_deref(), Some("26.1"));
+        assert_eq!(minecraft_version("26.1.1.2-beta").as_deref(), Some("26.1.1"));
+        assert_eq!(minecraft_version("0.25w14craftmine.3-beta").as_deref(), Some("25w14craftmine"));
+    }
+
+    #[test]
+    fn rejects_unknown_schemes() {
+        assert_eq!(minecraft_version("20.4"), None);
+        assert_eq!(minecraft_version("1.2.3.4.5"), None);
+        assert_eq!(minecraft_version("latest"), None);
+        assert_eq!(minecraft_version("0.25w14craftmine.x"), None);
+    }
+}
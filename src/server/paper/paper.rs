@@ -1,11 +1,22 @@
-use reqwest::get;
+use async_trait::async_trait;
 use serde::Deserialize;
+use std::path::Path;
 
-use crate::version::Version;
+use crate::cache;
+use crate::downloader::Checksum;
+use crate::error::{MineError, Result};
+use crate::manifest::Server;
+use crate::server::provider::{requirement, select, Progress, ResolvedServer, ServerArtifact, ServerProvider};
+use crate::server::proxy::{self, ProxyConfig};
+use crate::version::VersionReq;
 
-const PAPER_MANIFEST_URL: &str = "https://api.papermc.io/v2/projects/paper";
+const PAPERMC_API_URL: &str = "https://api.papermc.io/v2/projects";
+const PURPUR_API_URL: &str = "https://api.purpurmc.org/v2";
+const MOHIST_API_URL: &str = "https://mohistmc.com/api/v2/projects";
 
 // https://api.papermc.io/v2/projects/paper
+// https://api.purpurmc.org/v2/purpur
+// https://mohistmc.com/api/v2/projects/mohist
 #[derive(Deserialize)]
 struct PaperManifest {
     versions: Vec<String>,
@@ -17,6 +28,18 @@ struct PaperVersion {
     builds: Vec<u32>,
 }
 
+// https://api.papermc.io/v2/projects/paper/versions/1.16.5/builds
+#[derive(Deserialize)]
+struct PaperBuilds {
+    builds: Vec<PaperBuildSummary>,
+}
+
+#[derive(Deserialize)]
+struct PaperBuildSummary {
+    build: u32,
+    channel: String, // default or experimental
+}
+
 // https://api.papermc.io/v2/projects/paper/versions/1.16.5/builds/471
 #[derive(Deserialize)]
 struct PaperBuild {
@@ -31,58 +54,221 @@ struct Downloads {
 #[derive(Deserialize)]
 struct DownloadInfo {
     name: String,
+    sha256: String,
+}
+
+// https://api.purpurmc.org/v2/purpur/1.21
+#[derive(Deserialize)]
+struct PurpurVersion {
+    builds: PurpurBuilds,
+}
+
+#[derive(Deserialize)]
+struct PurpurBuilds {
+    all: Vec<String>,
 }
 
-async fn get_manifest() -> Result<PaperManifest, Box<dyn std::error::Error>> {
-    let response = get(PAPER_MANIFEST_URL).await?.text().await?;
-    let manifest: PaperManifest = serde_json::from_str(&response)?;
-    Ok(manifest)
+// https://api.purpurmc.org/v2/purpur/1.21/2233
+#[derive(Deserialize)]
+struct PurpurBuild {
+    md5: String,
+}
+
+// https://mohistmc.com/api/v2/projects/mohist/1.20.1/builds
+#[derive(Deserialize)]
+struct MohistBuilds {
+    builds: Vec<MohistBuild>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MohistBuild {
+    number: u32,
+    // https://mohistmc.com/api/v2/projects/mohist/1.20.1/builds/863/download
+    url: String,
+    #[serde(default)]
+    file_md5: Option<String>,
 }
 
-async fn get_latest_version() -> Result<String, Box<dyn std::error::Error>> {
-    let manifest = get_manifest().await?;
-    // the latest version is the last element
-    Ok(manifest.versions.last().unwrap().clone())
+// the flavour of API a project is served by; Purpur's and MohistMC's mirror the PaperMC v2 API with simpler responses
+#[derive(Clone, Copy)]
+enum Api {
+    PaperMc,
+    Purpur,
+    Mohist,
 }
 
-async fn get_latest_build(version: String) -> Result<u32, Box<dyn std::error::Error>> {
-    let response = get(format!("{}/versions/{}", PAPER_MANIFEST_URL, version)).await?.text().await?;
-    let paper_version: PaperVersion = serde_json::from_str(&response)?;
-    Ok(*paper_version.builds.last().unwrap())
+// a project served by the PaperMC v2 API, or by Purpur's or MohistMC's; MohistMC's are hybrid servers,
+// which run Bukkit plugins next to Forge (Mohist) or Fabric (Banner) mods
+pub struct Paper {
+    // the project name, also the type in mine.toml
+    project: &'static str,
+    name: &'static str,
+    base_url: &'static str,
+    api: Api,
+    // the configuration written for proxies, which have no EULA
+    proxy: Option<ProxyConfig>,
 }
 
-async fn get_build(version: String, build: u32) -> Result<PaperBuild, Box<dyn std::error::Error>> {
-    let response = get(format!("{}/versions/{}/builds/{}", PAPER_MANIFEST_URL, version, build)).await?.text().await?;
-    let paper_build: PaperBuild = serde_json::from_str(&response)?;
-    Ok(paper_build)
+impl Paper {
+    pub const PAPER: Paper = Paper { project: "paper", name: "Paper", base_url: PAPERMC_API_URL, api: Api::PaperMc, proxy: None };
+    pub const FOLIA: Paper = Paper { project: "folia", name: "Folia", base_url: PAPERMC_API_URL, api: Api::PaperMc, proxy: None };
+    pub const VELOCITY: Paper = Paper { project: "velocity", name: "Velocity", base_url: PAPERMC_API_URL, api: Api::PaperMc, proxy: Some(ProxyConfig::Velocity) };
+    pub const WATERFALL: Paper = Paper { project: "waterfall", name: "Waterfall", base_url: PAPERMC_API_URL, api: Api::PaperMc, proxy: Some(ProxyConfig::BungeeCord) };
+    pub const PURPUR: Paper = Paper { project: "purpur", name: "Purpur", base_url: PURPUR_API_URL, api: Api::Purpur, proxy: None };
+    pub const MOHIST: Paper = Paper { project: "mohist", name: "Mohist", base_url: MOHIST_API_URL, api: Api::Mohist, proxy: None };
+    pub const BANNER: Paper = Paper { project: "banner", name: "Banner", base_url: MOHIST_API_URL, api: Api::Mohist, proxy: None };
+
+    fn project_url(&self) -> String {
+        format!("{}/{}", self.base_url, self.project)
+    }
+
+    fn version_url(&self, version: &str) -> String {
+        match self.api {
+            Api::PaperMc => format!("{}/versions/{}", self.project_url(), version),
+            Api::Purpur | Api::Mohist => format!("{}/{}", self.project_url(), version),
+        }
+    }
+
+    fn build_url(&self, version: &str, build: u32) -> String {
+        match self.api {
+            Api::PaperMc => format!("{}/builds/{}", self.version_url(version), build),
+            Api::Purpur => format!("{}/{}", self.version_url(version), build),
+            Api::Mohist => format!("{}/builds/{}", self.version_url(version), build),
+        }
+    }
+
+    // builds of a MohistMC version, oldest first
+    async fn get_mohist_builds(&self, version: &str) -> Result<Vec<MohistBuild>> {
+        let mohist_builds: MohistBuilds = cache::fetch_json(&format!("{}/builds", self.version_url(version))).await?;
+        let mut builds = mohist_builds.builds;
+        builds.sort_by_key(|build| build.number);
+        Ok(builds)
+    }
+
+    // versions, oldest first
+    async fn get_versions(&self) -> Result<Vec<String>> {
+        let manifest: PaperManifest = cache::fetch_json(&self.project_url()).await?;
+        Ok(manifest.versions)
+    }
+
+    // builds of a version, oldest first
+    async fn get_builds(&self, version: &str) -> Result<Vec<u32>> {
+        match self.api {
+            Api::PaperMc => {
+                let paper_version: PaperVersion = cache::fetch_json(&self.version_url(version)).await?;
+                Ok(paper_version.builds)
+            },
+            Api::Purpur => {
+                let purpur_version: PurpurVersion = cache::fetch_json(&self.version_url(version)).await?;
+                Ok(purpur_version.builds.all.iter().filter_map(|build| build.parse().ok()).collect())
+            },
+            Api::Mohist => Ok(self.get_mohist_builds(version).await?.iter().map(|build| build.number).collect()),
+        }
+    }
+
+    // the newest build on the default channel; Purpur and MohistMC have no channels
+    async fn get_latest_stable_build(&self, version: &str) -> Result<u32> {
+        let latest_stable = match self.api {
+            Api::PaperMc => {
+                let paper_builds: PaperBuilds = cache::fetch_json(&format!("{}/builds", self.version_url(version))).await?;
+                paper_builds.builds.iter().rev().find(|b| b.channel == "default").map(|b| b.build)
+            },
+            Api::Purpur | Api::Mohist => self.get_builds(version).await?.last().copied(),
+        };
+        latest_stable.ok_or(MineError::BuildNotFound { version: version.to_string(), build: "latest-stable".to_string(), latest: None, suggestions: Vec::new() })
+    }
 }
 
-pub async fn get_download_link(version: Option<String>, build: Option<u32>) -> Result<(String, String), Box<dyn std::error::Error>> {
-    let latest_version = get_latest_version().await?;
-    let latest_build = get_latest_build(latest_version.clone()).await?;
+#[async_trait]
+impl ServerProvider for Paper {
+    fn kind(&self) -> &'static str {
+        self.project
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn needs_eula(&self) -> bool {
+        self.proxy.is_none()
+    }
 
-    let version = match version {
-        Some(v) if !v.is_empty() => v,
-        _ => latest_version.clone(),
-    };
+    fn plugin_target(&self) -> Option<(&'static str, &'static str)> {
+        match self.api {
+            // mods can be dropped into mods/ by hand
+            Api::Mohist => Some(("spigot", "plugins")),
+            // Modrinth uses the project names as loaders
+            Api::PaperMc | Api::Purpur => Some((self.project, "plugins")),
+        }
+    }
 
-    let build = match build {
-        Some(b) => b,
-        None => latest_build,
-    };
+    async fn list_versions(&self, _snapshots: bool) -> Result<Vec<String>> {
+        Ok(self.get_versions().await?.into_iter().rev().collect())
+    }
 
-    if Version::from_str(version.as_str()) > Version::from_str(latest_version.as_str()) {
-        return Err(format!("Version {} not found. Latest is {}", version, latest_version).into());
+    async fn list_builds(&self, version: &str) -> Result<Vec<u32>> {
+        Ok(self.get_builds(version).await?.into_iter().rev().collect())
     }
 
-    if build > latest_build {
-        return Err(format!("Build {} not found. Latest is {}", build, latest_build).into());
+    async fn resolve(&self, server: &Server) -> Result<ResolvedServer> {
+        let manifest = self.get_versions().await?;
+        let versions: Vec<&str> = manifest.iter().map(String::as_str).collect();
+
+        // the latest version is the last element
+        let req = requirement("version", &server.version)?;
+        let version = select(&req, &versions, &versions, versions.last().copied(), |version, latest, suggestions| MineError::VersionNotFound { what: "Minecraft version", version, latest, suggestions })?;
+
+        let builds: Vec<String> = self.get_builds(&version).await?.iter().map(u32::to_string).collect();
+        let builds: Vec<&str> = builds.iter().map(String::as_str).collect();
+
+        let build_req = requirement("build", server.build.as_deref().unwrap_or_default())?;
+        let latest_build = match build_req {
+            VersionReq::LatestStable => Some(self.get_latest_stable_build(&version).await?.to_string()),
+            _ => builds.last().map(|b| b.to_string()),
+        };
+        let build = select(&build_req, &builds, &builds, latest_build.as_deref(), |build, latest, suggestions| MineError::BuildNotFound { version: version.clone(), build, latest, suggestions })?;
+        let build = build.parse::<u32>().map_err(|e| MineError::parse(format!("build \"{}\"", build), e))?;
+
+        let mut resolved = ResolvedServer::new(self.kind(), version);
+        resolved.build = Some(build);
+        Ok(resolved)
     }
 
-    let paper_build = get_build(version.clone(), build).await?;
+    async fn artifacts(&self, resolved: &ResolvedServer) -> Result<Vec<ServerArtifact>> {
+        let build = resolved.build.ok_or_else(|| MineError::BuildNotFound { version: resolved.version.clone(), build: "none".to_string(), latest: None, suggestions: Vec::new() })?;
+        let build_url = self.build_url(&resolved.version, build);
+
+        let (url, checksum) = match self.api {
+            Api::PaperMc => {
+                let paper_build: PaperBuild = cache::fetch_json(&build_url).await?;
+                let application = paper_build.downloads.application;
+                (format!("{}/downloads/{}", build_url, application.name), Some(Checksum::Sha256(application.sha256)))
+            },
+            Api::Purpur => {
+                let purpur_build: PurpurBuild = cache::fetch_json(&build_url).await?;
+                (format!("{}/download", build_url), Some(Checksum::Md5(purpur_build.md5)))
+            },
+            // the build is a launcher jar that sets up its libraries on first start
+            Api::Mohist => {
+                let mohist_build = self.get_mohist_builds(&resolved.version).await?.into_iter()
+                    .find(|mohist_build| mohist_build.number == build)
+                    .ok_or_else(|| MineError::BuildNotFound { version: resolved.version.clone(), build: build.to_string(), latest: None, suggestions: Vec::new() })?;
+                (mohist_build.url, mohist_build.file_md5.map(Checksum::Md5))
+            },
+        };
 
-    Ok((
-        format!("{}/versions/{}/builds/{}/downloads/{}", PAPER_MANIFEST_URL, version, build, paper_build.downloads.application.name),
-        format!("(Version: {}, Build: {:?})", version, build)
-    ))
+        Ok(vec![ServerArtifact {
+            url,
+            path: "server.jar".to_string(),
+            checksum,
+        }])
+    }
+
+    async fn install(&self, server: &Server, _resolved: &ResolvedServer, dir: &Path, _progress: Progress<'_>) -> Result<()> {
+        match self.proxy {
+            Some(config) => proxy::write_config(config, dir, &server.backends),
+            None => Ok(()),
+        }
+    }
 }
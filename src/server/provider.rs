@@ -0,0 +1,187 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::downloader::Checksum;
+use crate::error::{MineError, Result};
+use crate::manifest::Server;
+use crate::server::arclight::arclight::Arclight;
+use crate::server::bedrock::bedrock::Bedrock;
+use crate::server::bungeecord::bungeecord::BungeeCord;
+use crate::server::fabric::fabric::Fabric;
+use crate::server::forge::forge::Forge;
+use crate::server::neoforge::neoforge::NeoForge;
+use crate::server::paper::paper::Paper;
+use crate::server::quilt::quilt::Quilt;
+use crate::server::spigot::spigot::Spigot;
+use crate::server::sponge::sponge::Sponge;
+use crate::server::vanilla::vanilla::Vanilla;
+use crate::version::{nearest, VersionReq};
+
+// a server with every version resolved
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResolvedServer {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loader: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installer: Option<String>,
+}
+
+impl ResolvedServer {
+    pub fn new(kind: &str, version: String) -> Self {
+        ResolvedServer {
+            kind: kind.to_string(),
+            version,
+            build: None,
+            loader: None,
+            installer: None,
+        }
+    }
+
+    // e.g. "(Version: 1.21, Build: 123)"
+    pub fn version_info(&self) -> String {
+        let mut info = format!("(Version: {}", self.version);
+        if let Some(build) = self.build {
+            info += &format!(", Build: {}", build);
+        }
+        if let Some(loader) = &self.loader {
+            info += &format!(", Loader: {}", loader);
+        }
+        if let Some(installer) = &self.installer {
+            info += &format!(", Installer: {}", installer);
+        }
+        info + ")"
+    }
+}
+
+// a file to download into the server directory
+pub struct ServerArtifact {
+    pub url: String,
+    // relative to the server directory
+    pub path: String,
+    pub checksum: Option<Checksum>,
+}
+
+// receives the lines an installer prints while it runs
+pub type Progress<'a> = &'a (dyn Fn(&str) + Send + Sync);
+
+#[async_trait]
+pub trait ServerProvider: Send + Sync {
+    // the type used in mine.toml, e.g. "paper"
+    fn kind(&self) -> &'static str;
+
+    // the display name, e.g. "Paper"
+    fn name(&self) -> &'static str;
+
+    // whether the Mojang EULA applies (it does not to proxies)
+    fn needs_eula(&self) -> bool {
+        true
+    }
+
+    // Modrinth loader used to pick plugin files, and the directory they go into
+    fn plugin_target(&self) -> Option<(&'static str, &'static str)> {
+        None
+    }
+
+    // available Minecraft versions, newest first
+    async fn list_versions(&self, snapshots: bool) -> Result<Vec<String>>;
+
+    // available builds of a Minecraft version, newest first
+    async fn list_builds(&self, _version: &str) -> Result<Vec<u32>> {
+        Err(MineError::Other(format!("{} servers have no builds", self.name())))
+    }
+
+    // available loader versions for a Minecraft version (empty for the latest), newest first
+    async fn list_loaders(&self, _version: &str, _unstable: bool) -> Result<Vec<String>> {
+        Err(MineError::Other(format!("{} servers have no loader versions", self.name())))
+    }
+
+    // available installer versions, newest first
+    async fn list_installers(&self, _unstable: bool) -> Result<Vec<String>> {
+        Err(MineError::Other(format!("{} servers have no installer versions", self.name())))
+    }
+
+    async fn resolve(&self, server: &Server) -> Result<ResolvedServer>;
+
+    async fn artifacts(&self, resolved: &ResolvedServer) -> Result<Vec<ServerArtifact>>;
+
+    // files install() builds in dir instead of downloading them, e.g. Spigot's server.jar;
+    // mine.lock records their hashes and `mine install --locked` checks them
+    fn built_artifacts(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    // sets up the server in dir once its artifacts are downloaded, e.g. by running an installer
+    async fn install(&self, _server: &Server, _resolved: &ResolvedServer, _dir: &Path, _progress: Progress<'_>) -> Result<()> {
+        Ok(())
+    }
+}
+
+// parses a version, loader, installer or build requirement from mine.toml or the command line
+pub fn requirement(what: &str, req: &str) -> Result<VersionReq> {
+    VersionReq::from_str(req).map_err(|e| MineError::parse(format!("{} requirement \"{}\"", what, req), e))
+}
+
+// picks what a requirement asks for: latest for "latest" and "latest-stable", the highest of the candidates
+// satisfying constraints, or an exact value. The value must be listed; when it is not (or nothing was picked),
+// not_found makes the error from the value, the latest one and the nearest listed values
+pub fn select(
+    req: &VersionReq,
+    candidates: &[&str],
+    listed: &[&str],
+    latest: Option<&str>,
+    not_found: impl Fn(String, Option<String>, Vec<String>) -> MineError,
+) -> Result<String> {
+    let selected = match req {
+        VersionReq::Latest | VersionReq::LatestStable => latest.map(str::to_string),
+        VersionReq::Exact(value) => Some(value.clone()),
+        VersionReq::Constraints(_) => req.select(candidates.iter().copied()).map(str::to_string),
+    };
+    let latest = latest.map(str::to_string);
+    let selected = selected.ok_or_else(|| not_found(req.to_string(), latest.clone(), Vec::new()))?;
+
+    if !listed.contains(&selected.as_str()) {
+        let suggestions = nearest(&selected, listed);
+        return Err(not_found(selected, latest, suggestions));
+    }
+    Ok(selected)
+}
+
+pub fn providers() -> Vec<Box<dyn ServerProvider>> {
+    vec![
+        Box::new(Vanilla),
+        Box::new(Paper::PAPER),
+        Box::new(Paper::FOLIA),
+        Box::new(Paper::PURPUR),
+        Box::new(Paper::VELOCITY),
+        Box::new(Paper::WATERFALL),
+        Box::new(BungeeCord),
+        Box::new(Spigot::SPIGOT),
+        Box::new(Spigot::CRAFTBUKKIT),
+        Box::new(Fabric::FABRIC),
+        Box::new(Fabric::LEGACY_FABRIC),
+        Box::new(Fabric::BABRIC),
+        Box::new(Quilt),
+        Box::new(Forge),
+        Box::new(NeoForge),
+        Box::new(Sponge::VANILLA),
+        Box::new(Sponge::FORGE),
+        Box::new(Paper::MOHIST),
+        Box::new(Paper::BANNER),
+        Box::new(Arclight),
+        Box::new(Bedrock),
+    ]
+}
+
+pub fn provider(kind: &str) -> Result<Box<dyn ServerProvider>> {
+    providers().into_iter().find(|p| p.kind() == kind).ok_or_else(|| {
+        let kinds: Vec<&str> = providers().iter().map(|p| p.kind()).collect();
+        MineError::Other(format!("Unknown server type {} (expected one of {})", kind, kinds.join(", ")))
+    })
+}
@@ -0,0 +1,142 @@
+// starter configurations for proxies, listing the backend servers from mine.toml
+
+use std::fs;
+use std::path::Path;
+
+use crate::error::Result;
+use crate::manifest::Backend;
+
+#[derive(Clone, Copy)]
+pub enum ProxyConfig {
+    // velocity.toml
+    Velocity,
+    // config.yml, shared by BungeeCord and its forks such as Waterfall
+    BungeeCord,
+}
+
+impl ProxyConfig {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ProxyConfig::Velocity => "velocity.toml",
+            ProxyConfig::BungeeCord => "config.yml",
+        }
+    }
+}
+
+// writes a starter configuration unless the proxy already has one, which is left to the user from then on
+pub fn write_config(config: ProxyConfig, dir: &Path, backends: &[Backend]) -> Result<()> {
+    let path = dir.join(config.file_name());
+    if path.exists() {
+        return Ok(());
+    }
+
+    // a placeholder so the proxy starts, the same as its own default
+    let lobby = [Backend { name: "lobby".to_string(), address: "127.0.0.1:25566".to_string() }];
+    let backends = if backends.is_empty() { &lobby[..] } else { backends };
+
+    let content = match config {
+        ProxyConfig::Velocity => velocity_toml(backends),
+        ProxyConfig::BungeeCord => bungeecord_yml(backends),
+    };
+    fs::write(path, content)?;
+    Ok(())
+}
+
+// toml basic string, also valid as a key, which toml's own formatting is not for names with quotes;
+// every JSON escape is a valid toml one
+fn toml_string(s: &str) -> String {
+    serde_json::Value::String(s.to_string()).to_string()
+}
+
+// yaml single-quoted string
+fn yaml_string(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+fn velocity_toml(backends: &[Backend]) -> String {
+    let mut toml = String::new();
+    toml.push_str("# generated by mine, see https://docs.papermc.io/velocity/configuration\n");
+    toml.push_str("config-version = \"2.7\"\n");
+    toml.push_str("bind = \"0.0.0.0:25577\"\n");
+    toml.push_str("online-mode = true\n");
+    // backends need velocity-support enabled in paper-global.yml, with the secret Velocity writes on first start
+    toml.push_str("player-info-forwarding-mode = \"modern\"\n");
+    toml.push_str("forwarding-secret-file = \"forwarding.secret\"\n");
+    toml.push_str("\n[servers]\n");
+    for backend in backends {
+        toml.push_str(&format!("{} = {}\n", toml_string(&backend.name), toml_string(&backend.address)));
+    }
+    let names: Vec<String> = backends.iter().map(|b| toml_string(&b.name)).collect();
+    toml.push_str(&format!("try = [{}]\n", names.join(", ")));
+    toml.push_str("\n[forced-hosts]\n");
+    toml
+}
+
+fn bungeecord_yml(backends: &[Backend]) -> String {
+    let mut yml = String::new();
+    yml.push_str("# generated by mine, see https://www.spigotmc.org/wiki/bungeecord-configuration-guide/\n");
+    yml.push_str("listeners:\n");
+    yml.push_str("- host: 0.0.0.0:25577\n");
+    yml.push_str("  priorities:\n");
+    for backend in backends {
+        yml.push_str(&format!("  - {}\n", yaml_string(&backend.name)));
+    }
+    yml.push_str("servers:\n");
+    for backend in backends {
+        yml.push_str(&format!("  {}:\n", yaml_string(&backend.name)));
+        yml.push_str(&format!("    address: {}\n", yaml_string(&backend.address)));
+        yml.push_str("    restricted: false\n");
+    }
+    yml.push_str("online_mode: true\n");
+    // backends need bungeecord: true in spigot.yml
+    yml.push_str("ip_forward: true\n");
+    yml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backends() -> Vec<Backend> {
+        vec![
+            Backend { name: "lobby".to_string(), address: "127.0.0.1:25566".to_string() },
+            Backend { name: "it's \"survival\"".to_string(), address: "10.0.0.2:25565".to_string() },
+        ]
+    }
+
+    #[test]
+    fn velocity_toml_lists_the_backends() {
+        let config: toml::Table = toml::from_str(&velocity_toml(&backends())).unwrap();
+        let servers = config["servers"].as_table().unwrap();
+        assert_eq!(servers["lobby"].as_str(), Some("127.0.0.1:25566"));
+        assert_eq!(servers["it's \"survival\""].as_str(), Some("10.0.0.2:25565"));
+        let try_order: Vec<&str> = servers["try"].as_array().unwrap().iter().filter_map(|name| name.as_str()).collect();
+        assert_eq!(try_order, ["lobby", "it's \"survival\""]);
+        assert_eq!(config["player-info-forwarding-mode"].as_str(), Some("modern"));
+        assert!(config["forced-hosts"].as_table().unwrap().is_empty());
+    }
+
+    #[test]
+    fn bungeecord_yml_lists_the_backends() {
+        let yml = bungeecord_yml(&backends());
+        assert!(yml.contains("  priorities:\n  - 'lobby'\n  - 'it''s \"survival\"'\nservers:\n"));
+        assert!(yml.contains("  'lobby':\n    address: '127.0.0.1:25566'\n    restricted: false\n"));
+        assert!(yml.contains("  'it''s \"survival\"':\n    address: '10.0.0.2:25565'\n"));
+        assert!(yml.ends_with("ip_forward: true\n"));
+    }
+
+    #[test]
+    fn existing_configs_are_kept() {
+        let dir = std::env::temp_dir().join(format!("mine-proxy-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(ProxyConfig::Velocity.file_name());
+        fs::write(&path, "edited").unwrap();
+        write_config(ProxyConfig::Velocity, &dir, &backends()).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "edited");
+
+        // the placeholder backend without any in mine.toml
+        write_config(ProxyConfig::BungeeCord, &dir, &[]).unwrap();
+        assert!(fs::read_to_string(dir.join("config.yml")).unwrap().contains("  'lobby':\n    address: '127.0.0.1:25566'\n"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
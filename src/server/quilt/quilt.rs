@@ -0,0 +1,71 @@
+use async_trait::async_trait;
+use std::path::Path;
+
+use crate::error::Result;
+use crate::manifest::Server;
+use crate::server::fabric::meta;
+use crate::server::provider::{Progress, ResolvedServer, ServerArtifact, ServerProvider};
+use crate::server::{java, maven};
+
+const QUILT_MANIFEST_URL: &str = "https://meta.quiltmc.org/v3/versions";
+const QUILT_INSTALLER_MAVEN_URL: &str = "https://maven.quiltmc.org/repository/release/org/quiltmc/quilt-installer";
+
+const INSTALLER_JAR: &str = "quilt-installer.jar";
+
+pub struct Quilt;
+
+#[async_trait]
+impl ServerProvider for Quilt {
+    fn kind(&self) -> &'static str {
+        "quilt"
+    }
+
+    fn name(&self) -> &'static str {
+        "Quilt"
+    }
+
+    fn plugin_target(&self) -> Option<(&'static str, &'static str)> {
+        Some(("quilt", "mods"))
+    }
+
+    async fn list_versions(&self, snapshots: bool) -> Result<Vec<String>> {
+        let manifest = meta::get_manifest(QUILT_MANIFEST_URL).await?;
+        Ok(meta::list(manifest.game, snapshots))
+    }
+
+    async fn list_loaders(&self, _version: &str, unstable: bool) -> Result<Vec<String>> {
+        let manifest = meta::get_manifest(QUILT_MANIFEST_URL).await?;
+        Ok(meta::list(manifest.loader, unstable))
+    }
+
+    async fn list_installers(&self, unstable: bool) -> Result<Vec<String>> {
+        let manifest = meta::get_manifest(QUILT_MANIFEST_URL).await?;
+        Ok(meta::list(manifest.installer, unstable))
+    }
+
+    async fn resolve(&self, server: &Server) -> Result<ResolvedServer> {
+        meta::resolve(self.kind(), QUILT_MANIFEST_URL, server).await
+    }
+
+    // unlike Fabric's, Quilt's meta API does not build server jars, so the installer is downloaded and run instead
+    async fn artifacts(&self, resolved: &ResolvedServer) -> Result<Vec<ServerArtifact>> {
+        let (_, installer) = meta::loader_and_installer(resolved)?;
+        let url = format!("{}/{}/quilt-installer-{}.jar", QUILT_INSTALLER_MAVEN_URL, installer, installer);
+        let checksum = maven::sha1(&url).await;
+
+        Ok(vec![ServerArtifact {
+            url,
+            path: INSTALLER_JAR.to_string(),
+            checksum,
+        }])
+    }
+
+    // produces quilt-server-launch.jar, the vanilla server.jar and start scripts
+    async fn install(&self, _server: &Server, resolved: &ResolvedServer, dir: &Path, progress: Progress<'_>) -> Result<()> {
+        let (loader, _) = meta::loader_and_installer(resolved)?;
+        java::run_jar(dir, INSTALLER_JAR, &[
+            "install", "server", &resolved.version, loader,
+            "--download-server", "--create-scripts", "--install-dir=.",
+        ], progress).await
+    }
+}
@@ -0,0 +1,191 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::BuildHasher;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::cache;
+use crate::downloader;
+use crate::error::{MineError, Result};
+use crate::manifest::Server;
+use crate::server::java;
+use crate::server::provider::{requirement, select, Progress, ResolvedServer, ServerArtifact, ServerProvider};
+use crate::server::vanilla::vanilla;
+use crate::version::{MinecraftVersion, VersionKind};
+use crate::warning;
+
+const SPIGOT_VERSIONS_URL: &str = "https://hub.spigotmc.org/versions";
+const BUILDTOOLS_JENKINS_URL: &str = "https://hub.spigotmc.org/jenkins/job/BuildTools";
+
+const BUILDTOOLS_JAR: &str = "BuildTools.jar";
+
+// https://hub.spigotmc.org/versions/1.21.json
+#[derive(Deserialize)]
+struct SpigotVersion {
+    // the Spigot build number, e.g. "4226"
+    name: String,
+    // the oldest and newest Java class file versions BuildTools may run on, e.g. [65, 66] for Java 21 and 22
+    #[serde(default, rename = "javaVersions")]
+    java_versions: Option<[u32; 2]>,
+}
+
+// https://hub.spigotmc.org/jenkins/job/BuildTools/lastSuccessfulBuild/api/json
+#[derive(Deserialize)]
+struct JenkinsBuild {
+    number: u32,
+}
+
+async fn get_version(version: &str) -> Result<SpigotVersion> {
+    cache::fetch_json(&format!("{}/{}.json", SPIGOT_VERSIONS_URL, version)).await
+}
+
+// Minecraft versions BuildTools has a revision for, newest first
+async fn get_versions(snapshots: bool) -> Result<Vec<String>> {
+    // a directory listing, which also has revisions named by build number ("4226.json") and "latest.json"
+    let index = cache::fetch(&format!("{}/", SPIGOT_VERSIONS_URL)).await?;
+    let mut versions: Vec<MinecraftVersion> = index.split("href=\"").skip(1)
+        .filter_map(|link| link.split('"').next()?.strip_suffix(".json"))
+        .map(MinecraftVersion::parse)
+        .filter(|version| version.is_release() || (snapshots && !matches!(version.kind, VersionKind::Other)))
+        .collect();
+    vanilla::sort_newest_first(&mut versions).await;
+    versions.dedup_by(|a, b| a.id == b.id);
+    Ok(versions.into_iter().map(|version| version.id).collect())
+}
+
+// a new directory for BuildTools that only this user can use; its name is random and an
+// existing directory is never reused, so nobody else can predict it or put files into it
+fn scratch_dir() -> Result<PathBuf> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+
+    let mut attempt = 0u32;
+    loop {
+        let scratch = std::env::temp_dir().join(format!("mine-buildtools-{:016x}", RandomState::new().hash_one(attempt)));
+        match builder.create(&scratch) {
+            Ok(()) => return Ok(scratch),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempt < 16 => attempt += 1,
+            Err(e) => return Err(MineError::Other(format!("Failed to create {}: {}", scratch.display(), e))),
+        }
+    }
+}
+
+// Spigot or CraftBukkit, which cannot be downloaded and are compiled with BuildTools instead
+pub struct Spigot {
+    kind: &'static str,
+    name: &'static str,
+    // BuildTools' --compile, also the prefix of the jar it produces
+    compile: &'static str,
+    // Modrinth loader
+    loader: &'static str,
+}
+
+impl Spigot {
+    pub const SPIGOT: Spigot = Spigot { kind: "spigot", name: "Spigot", compile: "spigot", loader: "spigot" };
+    pub const CRAFTBUKKIT: Spigot = Spigot { kind: "craftbukkit", name: "CraftBukkit", compile: "craftbukkit", loader: "bukkit" };
+
+    // builds are slow, so the jars are cached by revision
+    fn cache_key(&self, resolved: &ResolvedServer) -> String {
+        format!("buildtools:{}:{}:{}", self.compile, resolved.version, resolved.build.map(|b| b.to_string()).unwrap_or_default())
+    }
+
+    // downloads BuildTools into a scratch directory, runs it there and returns the jar it produced, e.g. spigot-1.21.jar
+    async fn build(&self, resolved: &ResolvedServer, progress: Progress<'_>) -> Result<PathBuf> {
+        // BuildTools refuses to run on a Java outside this range; revisions without one predate Java 9
+        let [oldest, newest] = get_version(&resolved.version).await?.java_versions.unwrap_or([51, 52]);
+        // class file version 52 is Java 8
+        let java = java::find(oldest.saturating_sub(44)..=newest.saturating_sub(44)).await?;
+
+        let buildtools = resolved.installer.as_ref().ok_or_else(|| MineError::InstallerNotFound { installer: "none".to_string(), latest: None, suggestions: Vec::new() })?;
+        let scratch = scratch_dir()?;
+        progress(&format!("Downloading {} #{}", BUILDTOOLS_JAR, buildtools));
+        // Jenkins publishes no checksum for its artifacts
+        let url = format!("{}/{}/artifact/target/{}", BUILDTOOLS_JENKINS_URL, buildtools, BUILDTOOLS_JAR);
+        downloader::download_quietly(&url, &scratch.join(BUILDTOOLS_JAR), None).await?;
+
+        java::run_jar_with(&java, &scratch, BUILDTOOLS_JAR, &[
+            "--rev", &resolved.version, "--compile", self.compile, "--output-dir", "out",
+        ], progress).await?;
+
+        let prefix = format!("{}-", self.compile);
+        let jar = fs::read_dir(scratch.join("out"))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .find(|path| path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".jar")))
+            .ok_or_else(|| MineError::Other(format!("BuildTools did not produce a {} jar, see {}.log", self.name, scratch.join(BUILDTOOLS_JAR).display())))?;
+        Ok(jar)
+    }
+}
+
+#[async_trait]
+impl ServerProvider for Spigot {
+    fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn plugin_target(&self) -> Option<(&'static str, &'static str)> {
+        Some((self.loader, "plugins"))
+    }
+
+    async fn list_versions(&self, snapshots: bool) -> Result<Vec<String>> {
+        get_versions(snapshots).await
+    }
+
+    async fn resolve(&self, server: &Server) -> Result<ResolvedServer> {
+        let listed = get_versions(true).await?;
+        let listed: Vec<&str> = listed.iter().map(String::as_str).collect();
+        let candidates: Vec<&str> = listed.iter().copied().filter(|id| server.snapshot || MinecraftVersion::parse(id).is_release()).collect();
+
+        let req = requirement("version", &server.version)?;
+        let version = select(&req, &candidates, &listed, candidates.first().copied(), |version, latest, suggestions| MineError::VersionNotFound { what: "Minecraft version", version, latest, suggestions })?;
+
+        // the revision's Spigot build, so the built jar can be cached, and the BuildTools build to use
+        let spigot_version = get_version(&version).await?;
+        let buildtools: JenkinsBuild = cache::fetch_json(&format!("{}/lastSuccessfulBuild/api/json", BUILDTOOLS_JENKINS_URL)).await?;
+
+        let mut resolved = ResolvedServer::new(self.kind(), version);
+        resolved.build = spigot_version.name.parse().ok();
+        resolved.installer = Some(buildtools.number.to_string());
+        Ok(resolved)
+    }
+
+    // BuildTools is only downloaded when the server has to be built, and not into the server directory
+    async fn artifacts(&self, _resolved: &ResolvedServer) -> Result<Vec<ServerArtifact>> {
+        Ok(Vec::new())
+    }
+
+    fn built_artifacts(&self) -> &'static [&'static str] {
+        &["server.jar"]
+    }
+
+    // compiles the server unless this revision was built before, and copies it to server.jar
+    async fn install(&self, _server: &Server, resolved: &ResolvedServer, dir: &Path, progress: Progress<'_>) -> Result<()> {
+        let key = self.cache_key(resolved);
+        if let Some(cached) = cache::lookup(&key, None) {
+            fs::copy(cached, dir.join("server.jar"))?;
+            return Ok(());
+        }
+
+        let jar = self.build(resolved, progress).await?;
+        fs::copy(&jar, dir.join("server.jar"))?;
+        if let Err(e) = cache::store_file(&jar, &key) {
+            warning::warn(&format!("Failed to cache {}: {}", jar.display(), e));
+        }
+
+        // kept when the build fails, for its log
+        if let Some(scratch) = jar.parent().and_then(Path::parent) {
+            fs::remove_dir_all(scratch).ok();
+        }
+        Ok(())
+    }
+}
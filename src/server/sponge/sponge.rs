@@ -0,0 +1,262 @@
+use async_trait::async_trait;
+use serde::de::{Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::cache;
+use crate::downloader::Checksum;
+use crate::error::{MineError, Result};
+use crate::manifest::Server;
+use crate::server::forge::forge::Forge;
+use crate::server::provider::{requirement, select, Progress, ResolvedServer, ServerArtifact, ServerProvider};
+use crate::server::vanilla::vanilla;
+use crate::version::{MinecraftVersion, VersionReq};
+
+const SPONGE_API_URL: &str = "https://dl-api.spongepowered.org/v2/groups/org.spongepowered/artifacts";
+
+// versions fetched per request
+const PAGE_SIZE: usize = 25;
+
+// https://dl-api.spongepowered.org/v2/groups/org.spongepowered/artifacts/spongevanilla
+#[derive(Deserialize)]
+struct SpongeArtifact {
+    // every value of each tag, e.g. "minecraft": ["1.20.6", ...] and "api": ["11.0.0", ...]
+    tags: HashMap<String, Vec<String>>,
+}
+
+// https://dl-api.spongepowered.org/v2/groups/org.spongepowered/artifacts/spongevanilla/versions?tags=minecraft:1.20.6
+#[derive(Deserialize)]
+struct SpongeVersions {
+    // newest first
+    #[serde(deserialize_with = "in_order")]
+    artifacts: Vec<(String, SpongeVersionSummary)>,
+    size: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpongeVersionSummary {
+    tag_values: HashMap<String, String>,
+    #[serde(default)]
+    recommended: bool,
+}
+
+// https://dl-api.spongepowered.org/v2/groups/org.spongepowered/artifacts/spongevanilla/versions/1.20.6-11.0.0
+#[derive(Deserialize)]
+struct SpongeVersion {
+    assets: Vec<SpongeAsset>,
+    // "minecraft", "api" and, for SpongeForge, "forge"
+    tags: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpongeAsset {
+    classifier: String,
+    extension: String,
+    download_url: String,
+    sha1: String,
+}
+
+// the entries of a JSON object in the order the API sent them, which a map would lose
+fn in_order<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Vec<(String, SpongeVersionSummary)>, D::Error> {
+    struct InOrder;
+
+    impl<'de> Visitor<'de> for InOrder {
+        type Value = Vec<(String, SpongeVersionSummary)>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a map of versions")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Self::Value, A::Error> {
+            let mut entries = Vec::new();
+            while let Some(entry) = map.next_entry()? {
+                entries.push(entry);
+            }
+            Ok(entries)
+        }
+    }
+
+    deserializer.deserialize_map(InOrder)
+}
+
+// an API version requirement; a bare one also matches newer patches of it ("11" matches "11.0.0")
+fn matches_api(req: &VersionReq, api: &str) -> bool {
+    match req {
+        VersionReq::Exact(exact) => api == exact || api.starts_with(&format!("{}.", exact)),
+        req => req.matches(api),
+    }
+}
+
+// SpongeVanilla, or SpongeForge which runs on a Forge server
+pub struct Sponge {
+    // the artifact id, also the type in mine.toml
+    artifact: &'static str,
+    name: &'static str,
+    forge: bool,
+}
+
+impl Sponge {
+    pub const VANILLA: Sponge = Sponge { artifact: "spongevanilla", name: "SpongeVanilla", forge: false };
+    pub const FORGE: Sponge = Sponge { artifact: "spongeforge", name: "SpongeForge", forge: true };
+
+    fn artifact_url(&self) -> String {
+        format!("{}/{}", SPONGE_API_URL, self.artifact)
+    }
+
+    // Minecraft versions with builds, newest first
+    async fn get_minecraft_versions(&self) -> Result<Vec<String>> {
+        let artifact: SpongeArtifact = cache::fetch_json(&self.artifact_url()).await?;
+        let mut versions: Vec<MinecraftVersion> = artifact.tags.get("minecraft").into_iter().flatten()
+            .map(|id| MinecraftVersion::parse(id))
+            .collect();
+        vanilla::sort_newest_first(&mut versions).await;
+        Ok(versions.into_iter().map(|version| version.id).collect())
+    }
+
+    // the builds of a Minecraft version, newest first, a page at a time
+    async fn get_versions(&self, version: &str) -> Result<Vec<(String, SpongeVersionSummary)>> {
+        let mut versions = Vec::new();
+        loop {
+            let url = format!("{}/versions?tags=minecraft:{}&offset={}&limit={}", self.artifact_url(), version, versions.len(), PAGE_SIZE);
+            let page: SpongeVersions = cache::fetch_json(&url).await?;
+            let done = page.artifacts.is_empty() || versions.len() + page.artifacts.len() >= page.size;
+            versions.extend(page.artifacts);
+            if done {
+                return Ok(versions);
+            }
+        }
+    }
+
+    async fn get_version(&self, version: &str) -> Result<SpongeVersion> {
+        cache::fetch_json(&format!("{}/versions/{}", self.artifact_url(), version)).await
+    }
+
+    // the newest build of a Minecraft version for an API version requirement;
+    // builds not marked recommended are release candidates, skipped unless allowed
+    async fn select_build(&self, version: &str, api_req: &VersionReq, unstable: bool) -> Result<String> {
+        let builds = self.get_versions(version).await?;
+        let candidates: Vec<&(String, SpongeVersionSummary)> = builds.iter().filter(|(_, summary)| unstable || summary.recommended).collect();
+        let latest_build = candidates.first().map(|(build, _)| build.clone());
+
+        let build = match api_req {
+            VersionReq::Latest | VersionReq::LatestStable => latest_build.clone(),
+            api_req => candidates.iter()
+                .find(|(_, summary)| summary.tag_values.get("api").is_some_and(|api| matches_api(api_req, api)))
+                .map(|(build, _)| build.clone()),
+        };
+        build.ok_or_else(|| MineError::LoaderNotFound {
+            loader: match api_req {
+                VersionReq::Latest | VersionReq::LatestStable => format!("recommended build for {}", version),
+                _ => format!("API {} for {}", api_req, version),
+            },
+            latest: latest_build,
+            // the newest build at all, when only release candidates are left
+            suggestions: builds.first().filter(|_| candidates.is_empty()).map(|(build, _)| build.clone()).into_iter().collect(),
+        })
+    }
+
+    // the Forge server SpongeForge is installed on
+    fn forge_server(resolved: &ResolvedServer, sponge_version: &SpongeVersion) -> Result<ResolvedServer> {
+        let forge_version = sponge_version.tags.get("forge")
+            .ok_or_else(|| MineError::LoaderNotFound { loader: format!("Forge for SpongeForge {}", resolved.loader.as_deref().unwrap_or_default()), latest: None, suggestions: Vec::new() })?;
+        let mut forge = ResolvedServer::new("forge", resolved.version.clone());
+        forge.loader = Some(forge_version.clone());
+        Ok(forge)
+    }
+}
+
+#[async_trait]
+impl ServerProvider for Sponge {
+    fn kind(&self) -> &'static str {
+        self.artifact
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn plugin_target(&self) -> Option<(&'static str, &'static str)> {
+        Some(("sponge", "mods"))
+    }
+
+    async fn list_versions(&self, snapshots: bool) -> Result<Vec<String>> {
+        let versions = self.get_minecraft_versions().await?;
+        Ok(versions.into_iter().filter(|id| snapshots || MinecraftVersion::parse(id).is_release()).collect())
+    }
+
+    // the builds of a Minecraft version, e.g. 1.20.6-11.0.0 or 1.20.6-11.0.0-RC1613
+    async fn list_loaders(&self, version: &str, unstable: bool) -> Result<Vec<String>> {
+        let version = match version {
+            "" => self.get_minecraft_versions().await?.into_iter().next().unwrap_or_default(),
+            version => version.to_string(),
+        };
+        Ok(self.get_versions(&version).await?.into_iter()
+            .filter(|(_, summary)| unstable || summary.recommended)
+            .map(|(version, _)| version)
+            .collect())
+    }
+
+    async fn resolve(&self, server: &Server) -> Result<ResolvedServer> {
+        let minecraft_versions = self.get_minecraft_versions().await?;
+        let minecraft_versions: Vec<&str> = minecraft_versions.iter().map(String::as_str).collect();
+        let releases: Vec<&str> = minecraft_versions.iter().copied().filter(|id| server.snapshot || MinecraftVersion::parse(id).is_release()).collect();
+
+        let req = requirement("version", &server.version)?;
+        let mut version = select(&req, &releases, &minecraft_versions, releases.first().copied(), |version, latest, suggestions| MineError::VersionNotFound { what: "Minecraft version", version, latest, suggestions })?;
+
+        let api_req = requirement("api", &server.loader)?;
+        let mut build = self.select_build(&version, &api_req, server.unstable_loader).await;
+
+        // new Minecraft versions start out with release candidates only, so without a version asked for
+        // the newest one with a matching build is used
+        if matches!(req, VersionReq::Latest | VersionReq::LatestStable) {
+            for older in releases.iter().skip(1) {
+                if build.is_ok() {
+                    break;
+                }
+                if let Ok(older_build) = self.select_build(older, &api_req, server.unstable_loader).await {
+                    version = older.to_string();
+                    build = Ok(older_build);
+                }
+            }
+        }
+        let build = build?;
+
+        let mut resolved = ResolvedServer::new(self.kind(), version);
+        resolved.loader = Some(build);
+        Ok(resolved)
+    }
+
+    async fn artifacts(&self, resolved: &ResolvedServer) -> Result<Vec<ServerArtifact>> {
+        let build = resolved.loader.as_ref().ok_or_else(|| MineError::LoaderNotFound { loader: "none".to_string(), latest: None, suggestions: Vec::new() })?;
+        let sponge_version = self.get_version(build).await?;
+        let universal = sponge_version.assets.iter()
+            .find(|asset| asset.classifier == "universal" && asset.extension == "jar")
+            .ok_or_else(|| MineError::Other(format!("{} {} has no server jar", self.name, build)))?;
+
+        let checksum = Some(Checksum::Sha1(universal.sha1.clone()));
+        if !self.forge {
+            return Ok(vec![ServerArtifact { url: universal.download_url.clone(), path: "server.jar".to_string(), checksum }]);
+        }
+
+        // SpongeForge is a mod of the Forge server it is built for
+        let mut artifacts = Forge.artifacts(&Self::forge_server(resolved, &sponge_version)?).await?;
+        artifacts.push(ServerArtifact {
+            url: universal.download_url.clone(),
+            path: format!("mods/spongeforge-{}.jar", build),
+            checksum,
+        });
+        Ok(artifacts)
+    }
+
+    async fn install(&self, server: &Server, resolved: &ResolvedServer, dir: &Path, progress: Progress<'_>) -> Result<()> {
+        match self.forge {
+            true => Forge.install(server, resolved, dir, progress).await,
+            false => Ok(()),
+        }
+    }
+}
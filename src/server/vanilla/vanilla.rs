@@ -1,6 +1,13 @@
-use reqwest::get;
+use async_trait::async_trait;
 use serde::Deserialize;
-use serde_json;
+use std::collections::HashMap;
+
+use crate::cache;
+use crate::downloader::Checksum;
+use crate::error::{MineError, Result};
+use crate::manifest::Server;
+use crate::server::provider::{requirement, select, ResolvedServer, ServerArtifact, ServerProvider};
+use crate::version::{MinecraftVersion, VersionReq};
 
 const VANILLA_MANIFEST_URL: &str = "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
 
@@ -19,7 +26,11 @@ struct VanillaLatest {
 #[derive(Deserialize)]
 struct VanillaVersion {
     id: String, // minecraft version
+    #[serde(rename = "type")]
+    kind: String, // release, snapshot, old_beta or old_alpha
     url: String,
+    #[serde(rename = "releaseTime")]
+    release_time: String,
 }
 
 #[derive(Deserialize)]
@@ -34,54 +45,85 @@ struct VanillaDownloads {
 
 #[derive(Deserialize)]
 struct VanillaServerDownloadInfo {
+    sha1: String,
     url: String,
 }
 
-async fn get_manifest() -> Result<VanillaManifest, Box<dyn std::error::Error>> {
-    let response = get(VANILLA_MANIFEST_URL).await?.text().await?;
-    let manifest: VanillaManifest = serde_json::from_str(&response)?;
-    Ok(manifest)
+async fn get_manifest() -> Result<VanillaManifest> {
+    cache::fetch_json(VANILLA_MANIFEST_URL).await
 }
 
-async fn get_artifact(version: String) -> Result<VanillaArtifact, Box<dyn std::error::Error>> {
+async fn get_artifact(version: String) -> Result<VanillaArtifact> {
     let manifest = get_manifest().await?;
     for ver in manifest.versions {
         if ver.id == version {
-            let version_artifact = get(&ver.url).await?.json::<VanillaArtifact>().await?;
-            return Ok(version_artifact);
+            return cache::fetch_json(&ver.url).await;
         }
     }
 
-    Err("Version not found".into())
+    Err(MineError::VersionNotFound { what: "Minecraft version", version, latest: Some(manifest.latest.release), suggestions: Vec::new() })
 }
 
-async fn get_latest_version(snapshot: bool) -> Result<String, Box<dyn std::error::Error>> {
-    let manifest = get_manifest().await?;
-    Ok(if snapshot { manifest.latest.snapshot } else { manifest.latest.release })
+// sorts Minecraft versions from another upstream newest first, by their release times from Mojang's manifest
+// so that snapshots and pre-releases fall between the right releases; versions the manifest does not list,
+// or all of them when it is unavailable, are ordered by their ids after the listed ones
+pub async fn sort_newest_first(versions: &mut [MinecraftVersion]) {
+    if let Ok(manifest) = get_manifest().await {
+        let release_times: HashMap<&str, &str> = manifest.versions.iter().map(|v| (v.id.as_str(), v.release_time.as_str())).collect();
+        for version in versions.iter_mut() {
+            if let Some(release_time) = release_times.get(version.id.as_str()) {
+                *version = MinecraftVersion::with_release_time(&version.id, release_time);
+            }
+        }
+    }
+    versions.sort_by(|a, b| b.cmp(a));
 }
 
-pub async fn get_download_link(version: Option<String>, snapshot: bool) -> Result<(String, String), Box<dyn std::error::Error>> {
-    let latest_stable_version = get_latest_version(false).await?;
-    let latest_snapshot_version = get_latest_version(true).await?;
+pub struct Vanilla;
 
-    let version = match version {
-        Some(ref v) if !v.is_empty() => Some(v.clone()),
-        _ => Some(if snapshot { latest_snapshot_version.clone() } else { latest_stable_version.clone() })
-    };
+#[async_trait]
+impl ServerProvider for Vanilla {
+    fn kind(&self) -> &'static str {
+        "vanilla"
+    }
 
-    let version_id = version.unwrap();
-    let manifest = get_manifest().await?;
+    fn name(&self) -> &'static str {
+        "Vanilla"
+    }
 
-    for ver in manifest.versions {
-        if ver.id == version_id {
-            let version_artifact = get_artifact(ver.id.clone()).await?;
-            return Ok((
-                version_artifact.downloads.server.url,
-                format!("({})", ver.id),
-            ));
-        }
+    async fn list_versions(&self, snapshots: bool) -> Result<Vec<String>> {
+        let manifest = get_manifest().await?;
+        let mut versions: Vec<MinecraftVersion> = manifest.versions.iter()
+            .filter(|v| snapshots || v.kind == "release")
+            .map(|v| MinecraftVersion::with_release_time(&v.id, &v.release_time))
+            .collect();
+        versions.sort_by(|a, b| b.cmp(a));
+        Ok(versions.into_iter().map(|version| version.id).collect())
     }
 
-    let latest_minecraft_version = if snapshot { latest_snapshot_version } else { latest_stable_version };
-    return Err(format!("Minecraft version {} not found. Latest is {}", version_id, latest_minecraft_version).into());
+    async fn resolve(&self, server: &Server) -> Result<ResolvedServer> {
+        let manifest = get_manifest().await?;
+        let ids: Vec<&str> = manifest.versions.iter().map(|v| v.id.as_str()).collect();
+        let candidates: Vec<&str> = manifest.versions.iter()
+            .filter(|v| server.snapshot || v.kind == "release")
+            .map(|v| v.id.as_str())
+            .collect();
+
+        let req = requirement("version", &server.version)?;
+        let latest = match req {
+            VersionReq::Latest if server.snapshot => &manifest.latest.snapshot,
+            _ => &manifest.latest.release,
+        };
+        let version = select(&req, &candidates, &ids, Some(latest), |version, latest, suggestions| MineError::VersionNotFound { what: "Minecraft version", version, latest, suggestions })?;
+        Ok(ResolvedServer::new(self.kind(), version))
+    }
+
+    async fn artifacts(&self, resolved: &ResolvedServer) -> Result<Vec<ServerArtifact>> {
+        let version_artifact = get_artifact(resolved.version.clone()).await?;
+        Ok(vec![ServerArtifact {
+            url: version_artifact.downloads.server.url,
+            path: "server.jar".to_string(),
+            checksum: Some(Checksum::Sha1(version_artifact.downloads.server.sha1)),
+        }])
+    }
 }
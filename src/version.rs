@@ -1,52 +1,559 @@
+use chrono::{DateTime, FixedOffset};
 use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Version {
     major: u32,
     minor: u32,
     patch: Option<u32>,
+    // Bedrock versions have a fourth number, e.g. 1.21.2.02
+    revision: Option<u32>,
 }
 
-impl Version {
-    pub fn from_str(version: &str) -> Result<Self, String> {
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(version: &str) -> Result<Self, String> {
         let parts: Vec<&str> = version.split('.').collect();
-        if parts.len() < 2 || parts.len() > 3 {
+        if parts.len() > 4 {
             return Err("Invalid version format".to_string());
         }
 
+        // a single number (such as a build number) is major.0
         let major = parts[0].parse::<u32>().map_err(|_| "Invalid major version".to_string())?;
-        let minor = parts[1].parse::<u32>().map_err(|_| "Invalid minor version".to_string())?;
-        let patch = if parts.len() == 3 {
-            Some(parts[2].parse::<u32>().map_err(|_| "Invalid patch version".to_string())?)
-        } else {
-            None
+        let minor = match parts.get(1) {
+            Some(minor) => minor.parse::<u32>().map_err(|_| "Invalid minor version".to_string())?,
+            None => 0,
+        };
+        let patch = match parts.get(2) {
+            Some(patch) => Some(patch.parse::<u32>().map_err(|_| "Invalid patch version".to_string())?),
+            None => None,
+        };
+        let revision = match parts.get(3) {
+            Some(revision) => Some(revision.parse::<u32>().map_err(|_| "Invalid revision".to_string())?),
+            None => None,
         };
 
-        Ok(Version { major, minor, patch })
+        Ok(Version { major, minor, patch, revision })
+    }
+}
+
+impl Version {
+    // missing numbers count as 0, so 1.21 == 1.21.0 as with MinecraftVersion
+    fn numbers(&self) -> (u32, u32, u32, u32) {
+        (self.major, self.minor, self.patch.unwrap_or(0), self.revision.unwrap_or(0))
     }
 }
 
 impl PartialEq for Version {
     fn eq(&self, other: &Self) -> bool {
-        self.major == other.major && self.minor == other.minor && self.patch == other.patch
+        self.numbers() == other.numbers()
     }
 }
 
 impl PartialOrd for Version {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        if self.major != other.major {
-            return Some(self.major.cmp(&other.major));
+        Some(self.numbers().cmp(&other.numbers()))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        for number in [self.patch, self.revision].into_iter().flatten() {
+            write!(f, ".{}", number)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    // ^1.20 is >=1.20, <2.0 and ^0.15 is >=0.15, <0.16
+    Caret,
+    // ~1.20.4 is >=1.20.4, <1.21
+    Tilde,
+}
+
+#[derive(Debug, Clone)]
+pub struct Comparator {
+    op: Op,
+    version: Version,
+}
+
+impl Comparator {
+    fn matches(&self, version: &Version) -> bool {
+        let v = &self.version;
+        match self.op {
+            Op::Eq => version == v,
+            Op::Gt => version > v,
+            Op::Ge => version >= v,
+            Op::Lt => version < v,
+            Op::Le => version <= v,
+            Op::Caret => {
+                let upper = if v.major > 0 {
+                    Version { major: v.major + 1, minor: 0, patch: None, revision: None }
+                } else {
+                    Version { major: 0, minor: v.minor + 1, patch: None, revision: None }
+                };
+                version >= v && *version < upper
+            },
+            Op::Tilde => version >= v && *version < Version { major: v.major, minor: v.minor + 1, patch: None, revision: None },
+        }
+    }
+}
+
+// A requested version (or loader, installer or build) as written in mine.toml or on the command line:
+// "" or "latest", "latest-stable", an exact id such as "1.21" or "24w14a",
+// or comma separated constraints such as ">=1.20.4, <1.21", "^0.15" or "~1.20.4"
+#[derive(Debug, Clone)]
+pub enum VersionReq {
+    Latest,
+    LatestStable,
+    Exact(String),
+    Constraints(Vec<Comparator>),
+}
+
+impl FromStr for VersionReq {
+    type Err = String;
+
+    fn from_str(req: &str) -> Result<Self, String> {
+        let req = req.trim();
+        match req {
+            "" | "latest" => return Ok(VersionReq::Latest),
+            "latest-stable" => return Ok(VersionReq::LatestStable),
+            _ => {},
+        }
+
+        if !req.starts_with(['>', '<', '=', '^', '~']) && !req.contains(',') {
+            return Ok(VersionReq::Exact(req.to_string()));
+        }
+
+        let comparators = req.split(',').map(|part| {
+            let part = part.trim();
+            let (op, version) = [(">=", Op::Ge), ("<=", Op::Le), (">", Op::Gt), ("<", Op::Lt), ("=", Op::Eq), ("^", Op::Caret), ("~", Op::Tilde)]
+                .iter()
+                .find_map(|(prefix, op)| part.strip_prefix(prefix).map(|v| (*op, v)))
+                .unwrap_or((Op::Eq, part));
+            let version = Version::from_str(version.trim()).map_err(|e| format!("{} in \"{}\"", e, part))?;
+            Ok(Comparator { op, version })
+        }).collect::<Result<Vec<_>, String>>()?;
+
+        Ok(VersionReq::Constraints(comparators))
+    }
+}
+
+impl VersionReq {
+    // whether a concrete version satisfies the requirement; "latest" and "latest-stable" are up to the provider
+    pub fn matches(&self, version: &str) -> bool {
+        match self {
+            VersionReq::Latest | VersionReq::LatestStable => true,
+            VersionReq::Exact(exact) => exact == version,
+            VersionReq::Constraints(comparators) => match Version::from_str(version) {
+                Ok(version) => comparators.iter().all(|c| c.matches(&version)),
+                Err(_) => false,
+            },
+        }
+    }
+
+    // the highest of the candidates satisfying the constraints
+    pub fn select<'a>(&self, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+        candidates.into_iter()
+            .filter(|candidate| self.matches(candidate))
+            .filter_map(|candidate| Version::from_str(candidate).ok().map(|version| (candidate, version)))
+            .fold(None, |best: Option<(&str, Version)>, (candidate, version)| match best {
+                Some((_, ref best_version)) if *best_version >= version => best,
+                _ => Some((candidate, version)),
+            })
+            .map(|(candidate, _)| candidate)
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VersionReq::Latest => write!(f, "latest"),
+            VersionReq::LatestStable => write!(f, "latest-stable"),
+            VersionReq::Exact(exact) => write!(f, "{}", exact),
+            VersionReq::Constraints(comparators) => {
+                let parts: Vec<String> = comparators.iter().map(|c| {
+                    let op = match c.op {
+                        Op::Eq => "=",
+                        Op::Gt => ">",
+                        Op::Ge => ">=",
+                        Op::Lt => "<",
+                        Op::Le => "<=",
+                        Op::Caret => "^",
+                        Op::Tilde => "~",
+                    };
+                    format!("{}{}", op, c.version)
+                }).collect();
+                write!(f, "{}", parts.join(", "))
+            },
+        }
+    }
+}
+
+// the candidates closest to a version that does not exist, for "did you mean" hints: the nearest lower
+// and higher version when it parses as one (1.20.7 -> 1.20.6, 1.21), otherwise the closest spellings
+pub fn nearest(requested: &str, candidates: &[&str]) -> Vec<String> {
+    if let Ok(requested) = Version::from_str(requested) {
+        let versions: Vec<(&str, Version)> = candidates.iter()
+            .filter_map(|candidate| Version::from_str(candidate).ok().map(|version| (*candidate, version)))
+            .collect();
+        let cmp = |a: &&(&str, Version), b: &&(&str, Version)| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal);
+        let below = versions.iter().filter(|(_, version)| *version < requested).max_by(cmp);
+        let above = versions.iter().filter(|(_, version)| *version > requested).min_by(cmp);
+
+        let nearest: Vec<String> = below.into_iter().chain(above).map(|(candidate, _)| candidate.to_string()).collect();
+        if !nearest.is_empty() {
+            return nearest;
+        }
+    }
+
+    let max_distance = requested.chars().count() / 3 + 1;
+    let mut close: Vec<(usize, &str)> = candidates.iter()
+        .map(|candidate| (edit_distance(requested, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    close.sort_by_key(|(distance, _)| *distance);
+    close.into_iter().take(3).map(|(_, candidate)| candidate.to_string()).collect()
+}
+
+// Levenshtein distance
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// what a Minecraft version id looks like, used to order versions when no release time is known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionKind {
+    // 1.21, 1.20.6
+    Release(Vec<u32>),
+    // 1.21-pre3, 1.14 Pre-Release 2
+    PreRelease(Vec<u32>, u32),
+    // 1.21-rc1
+    ReleaseCandidate(Vec<u32>, u32),
+    // 24w14a
+    Snapshot { year: u32, week: u32, suffix: String },
+    // b1.7.3, b1.1_02
+    Beta(Vec<u32>),
+    // a1.2.6
+    Alpha(Vec<u32>),
+    // classic, indev, infdev and April Fools versions such as c0.30_01c, inf-20100618 or 3D Shareware v1.34
+    Other,
+}
+
+// a version id from Mojang's version manifest
+#[derive(Debug, Clone)]
+pub struct MinecraftVersion {
+    pub id: String,
+    pub kind: VersionKind,
+    // "releaseTime" from the version manifest, which orders every kind of version
+    pub release_time: Option<DateTime<FixedOffset>>,
+}
+
+// "1.20.6" or "1.1_02" as [1, 20, 6] or [1, 1, 2], followed by whatever comes after the numbers
+fn split_numbers(id: &str) -> Option<(Vec<u32>, &str)> {
+    let end = id.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '_')).unwrap_or(id.len());
+    let (numbers, rest) = id.split_at(end);
+    let numbers: Vec<u32> = numbers.split(['.', '_']).map(|n| n.parse().ok()).collect::<Option<_>>()?;
+    if numbers.len() < 2 {
+        return None;
+    }
+    Some((numbers, rest))
+}
+
+fn parse_kind(id: &str) -> VersionKind {
+    // 24w14a
+    if let Some((year, rest)) = id.split_once('w') {
+        let week_end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        if let (Ok(year), Ok(week)) = (year.parse(), rest[..week_end].parse()) {
+            return VersionKind::Snapshot { year, week, suffix: rest[week_end..].to_string() };
+        }
+    }
+
+    if let Some(beta) = id.strip_prefix('b') {
+        return match split_numbers(beta) {
+            Some((numbers, "")) => VersionKind::Beta(numbers),
+            _ => VersionKind::Other,
+        };
+    }
+
+    if let Some(alpha) = id.strip_prefix('a') {
+        return match split_numbers(alpha) {
+            Some((numbers, "")) => VersionKind::Alpha(numbers),
+            _ => VersionKind::Other,
+        };
+    }
+
+    let Some((numbers, rest)) = split_numbers(id) else {
+        return VersionKind::Other;
+    };
+
+    let stage = |prefixes: &[&str]| {
+        prefixes.iter().find_map(|prefix| rest.strip_prefix(prefix)).and_then(|n| n.parse::<u32>().ok())
+    };
+
+    if rest.is_empty() {
+        VersionKind::Release(numbers)
+    } else if let Some(n) = stage(&["-pre", " Pre-Release "]) {
+        VersionKind::PreRelease(numbers, n)
+    } else if let Some(n) = stage(&["-rc"]) {
+        VersionKind::ReleaseCandidate(numbers, n)
+    } else {
+        VersionKind::Other
+    }
+}
+
+// compares version numbers, treating missing trailing numbers as 0 (1.21 == 1.21.0)
+fn cmp_numbers(a: &[u32], b: &[u32]) -> Ordering {
+    let len = a.len().max(b.len());
+    (0..len)
+        .map(|i| a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)))
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+impl MinecraftVersion {
+    pub fn parse(id: &str) -> Self {
+        MinecraftVersion {
+            id: id.to_string(),
+            kind: parse_kind(id),
+            release_time: None,
+        }
+    }
+
+    // a version as listed in Mojang's manifest, with its RFC 3339 "releaseTime"
+    pub fn with_release_time(id: &str, release_time: &str) -> Self {
+        MinecraftVersion {
+            release_time: DateTime::parse_from_rfc3339(release_time).ok(),
+            ..MinecraftVersion::parse(id)
         }
+    }
+
+    pub fn is_release(&self) -> bool {
+        matches!(self.kind, VersionKind::Release(_))
+    }
 
-        if self.minor != other.minor {
-            return Some(self.minor.cmp(&other.minor));
+    // where a kind of version sits in a release cycle, which ends with the release itself
+    fn stage(&self) -> Option<(&[u32], u32, u32)> {
+        match &self.kind {
+            VersionKind::PreRelease(numbers, n) => Some((numbers, 0, *n)),
+            VersionKind::ReleaseCandidate(numbers, n) => Some((numbers, 1, *n)),
+            VersionKind::Release(numbers) => Some((numbers, 2, 0)),
+            _ => None,
         }
+    }
+}
+
+impl fmt::Display for MinecraftVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+impl PartialEq for MinecraftVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MinecraftVersion {}
+
+impl PartialOrd for MinecraftVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Versions with a release time are ordered by it and come after those without, which are ordered by
+// their ids alone: other ids < alpha < beta < snapshots < releases (with their pre-releases and release
+// candidates). Ids tell apart anything else that compares equal, so this is a total order.
+impl Ord for MinecraftVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let era = |kind: &VersionKind| match kind {
+            VersionKind::Other => 0,
+            VersionKind::Alpha(_) => 1,
+            VersionKind::Beta(_) => 2,
+            VersionKind::Snapshot { .. } => 3,
+            VersionKind::Release(_) | VersionKind::PreRelease(..) | VersionKind::ReleaseCandidate(..) => 4,
+        };
+
+        let by_id = || match (&self.kind, &other.kind) {
+            (VersionKind::Snapshot { year: y1, week: w1, suffix: s1 }, VersionKind::Snapshot { year: y2, week: w2, suffix: s2 }) => {
+                (y1, w1, s1).cmp(&(y2, w2, s2))
+            },
+            (VersionKind::Alpha(a), VersionKind::Alpha(b)) | (VersionKind::Beta(a), VersionKind::Beta(b)) => cmp_numbers(a, b),
+            (a, b) if era(a) != era(b) => era(a).cmp(&era(b)),
+            _ => match (self.stage(), other.stage()) {
+                (Some((a, a_stage, a_n)), Some((b, b_stage, b_n))) => cmp_numbers(a, b).then(a_stage.cmp(&b_stage)).then(a_n.cmp(&b_n)),
+                _ => Ordering::Equal,
+            },
+        };
+
+        self.release_time.cmp(&other.release_time)
+            .then_with(by_id)
+            .then_with(|| self.id.cmp(&other.id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(ids: &[&str]) -> Vec<String> {
+        let mut versions: Vec<MinecraftVersion> = ids.iter().map(|id| MinecraftVersion::parse(id)).collect();
+        versions.sort();
+        versions.into_iter().map(|version| version.id).collect()
+    }
+
+    #[test]
+    fn missing_numbers_are_zero() {
+        let v = |version: &str| Version::from_str(version).unwrap();
+        assert!(v("1.21") == v("1.21.0"));
+        assert!(v("1.21.0") == v("1.21.0.0"));
+        assert!(v("1.21") < v("1.21.1"));
+        assert!(v("1.21.0.1") > v("1.21"));
+        assert!(VersionReq::from_str("=1.21").unwrap().matches("1.21.0"));
+        assert!(!VersionReq::from_str("<1.21").unwrap().matches("1.21.0"));
+        assert!(VersionReq::from_str(">=1.21.0").unwrap().matches("1.21"));
+    }
+
+    #[test]
+    fn minecraft_versions_order_by_id() {
+        let ordered = ["inf-20100618", "a1.2.6", "b1.1_02", "b1.7.3", "23w51b", "24w14a", "1.20.6", "1.21-pre3", "1.21-rc1", "1.21", "1.21.0"];
+        let mut shuffled = ordered;
+        shuffled.reverse();
+        shuffled.swap(1, 7);
+        shuffled.swap(3, 9);
+        assert_eq!(sorted(&shuffled), ordered);
+        assert_eq!(sorted(&ordered), ordered);
+    }
+
+    #[test]
+    fn minecraft_versions_order_by_release_time() {
+        let snapshot = MinecraftVersion::with_release_time("24w14a", "2024-04-03T12:00:00+00:00");
+        let release = MinecraftVersion::with_release_time("1.20.6", "2024-04-29T12:00:00+00:00");
+        let earlier_release = MinecraftVersion::with_release_time("1.20.4", "2023-12-07T12:00:00+00:00");
+        let unlisted = MinecraftVersion::parse("1.7.10_pre4");
+
+        let mut versions = vec![release.clone(), unlisted.clone(), snapshot.clone(), earlier_release.clone()];
+        versions.sort_by(|a, b| b.cmp(a));
+        assert_eq!(versions, vec![release, snapshot, earlier_release, unlisted]);
+    }
+
+    #[test]
+    fn version_reqs_parse() {
+        let req = |req: &str| VersionReq::from_str(req).unwrap().to_string();
+        assert_eq!(req(""), "latest");
+        assert_eq!(req(" latest "), "latest");
+        assert_eq!(req("latest-stable"), "latest-stable");
+        assert_eq!(req("24w14a"), "24w14a");
+        assert_eq!(req("1.21"), "1.21");
+        assert_eq!(req(">=1.20.4, <1.21"), ">=1.20.4, <1.21");
+        assert_eq!(req("1.20.4,<=1.21"), "=1.20.4, <=1.21");
+        assert_eq!(req("^0.15"), "^0.15");
+        assert_eq!(req("~ 1.20.4"), "~1.20.4");
+        assert!(matches!(VersionReq::from_str("1.21").unwrap(), VersionReq::Exact(_)));
+        assert!(VersionReq::from_str(">=1.x").is_err());
+        assert!(VersionReq::from_str(">=1.2.3.4.5").is_err());
+        assert!(VersionReq::from_str("1.20,").is_err());
+    }
+
+    #[test]
+    fn version_reqs_match() {
+        let matches = |req: &str, version: &str| VersionReq::from_str(req).unwrap().matches(version);
+        assert!(matches("latest", "anything"));
+        assert!(matches("latest-stable", "1.21"));
+        assert!(matches("24w14a", "24w14a"));
+        assert!(!matches("1.21", "1.21.0"));
+        assert!(matches(">=1.20.4, <1.21", "1.20.6"));
+        assert!(!matches(">=1.20.4, <1.21", "1.21"));
+        assert!(!matches(">1.20.4", "1.20.4"));
+        assert!(matches("<=1.20.4", "1.20.4"));
+        assert!(matches("^1.20", "1.99.9"));
+        assert!(!matches("^1.20", "2.0"));
+        assert!(matches("^0.15", "0.15.11"));
+        assert!(!matches("^0.15", "0.16.0"));
+        assert!(matches("~1.20.4", "1.20.6"));
+        assert!(!matches("~1.20.4", "1.20.3"));
+        assert!(!matches("~1.20.4", "1.21"));
+        assert!(!matches(">=1.20", "24w14a"));
+    }
+
+    #[test]
+    fn version_reqs_select_the_highest_match() {
+        let candidates = ["1.20.4", "1.21", "24w14a", "1.20.6", "1.19.4", "1.21.1"];
+        let select = |req: &str| VersionReq::from_str(req).unwrap().select(candidates);
+        assert_eq!(select("<1.21"), Some("1.20.6"));
+        assert_eq!(select("~1.20.4"), Some("1.20.6"));
+        assert_eq!(select(">=1.20"), Some("1.21.1"));
+        assert_eq!(select("^1.19, <1.20"), Some("1.19.4"));
+        assert_eq!(select(">1.21.1"), None);
+        // the first of equal versions
+        assert_eq!(VersionReq::from_str(">=1.21").unwrap().select(["1.21", "1.21.0"]), Some("1.21"));
+    }
+
+    #[test]
+    fn nearest_versions_are_either_side() {
+        let candidates = ["1.20.4", "1.20.6", "1.21", "24w14a"];
+        assert_eq!(nearest("1.20.5", &candidates), ["1.20.4", "1.20.6"]);
+        assert_eq!(nearest("1.20.7", &candidates), ["1.20.6", "1.21"]);
+        assert_eq!(nearest("1.22", &candidates), ["1.21"]);
+        assert_eq!(nearest("1.19", &candidates), ["1.20.4"]);
+        assert!(nearest("1.20", &[]).is_empty());
+    }
+
+    #[test]
+    fn nearest_spellings_without_versions() {
+        let candidates = ["24w14a", "24w13a", "1.20.6", "release"];
+        assert_eq!(nearest("24w14b", &candidates), ["24w14a", "24w13a"]);
+        assert_eq!(nearest("relaese", &candidates), ["release"]);
+        assert!(nearest("something", &candidates).is_empty());
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("1.21", "1.21"), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("24w14a", "24w14b"), 1);
+        assert_eq!(edit_distance("relaese", "release"), 2);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
 
-        match (self.patch, other.patch) {
-            (Some(p1), Some(p2)) => Some(p1.cmp(&p2)),
-            (Some(_), None) => Some(Ordering::Greater),
-            (None, Some(_)) => Some(Ordering::Less),
-            (None, None) => Some(Ordering::Equal),
+    #[test]
+    fn version_kinds() {
+        assert_eq!(parse_kind("1.21"), VersionKind::Release(vec![1, 21]));
+        assert_eq!(parse_kind("1.20.6"), VersionKind::Release(vec![1, 20, 6]));
+        assert_eq!(parse_kind("1.21-pre3"), VersionKind::PreRelease(vec![1, 21], 3));
+        assert_eq!(parse_kind("1.14 Pre-Release 2"), VersionKind::PreRelease(vec![1, 14], 2));
+        assert_eq!(parse_kind("1.21-rc1"), VersionKind::ReleaseCandidate(vec![1, 21], 1));
+        assert_eq!(parse_kind("24w14a"), VersionKind::Snapshot { year: 24, week: 14, suffix: "a".to_string() });
+        assert_eq!(parse_kind("b1.7.3"), VersionKind::Beta(vec![1, 7, 3]));
+        assert_eq!(parse_kind("b1.1_02"), VersionKind::Beta(vec![1, 1, 2]));
+        assert_eq!(parse_kind("a1.2.6"), VersionKind::Alpha(vec![1, 2, 6]));
+        for id in ["c0.30_01c", "inf-20100618", "3D Shareware v1.34", "1.7.10_pre4", "1", "b1.7.3x", "latest"] {
+            assert_eq!(parse_kind(id), VersionKind::Other, "{}", id);
         }
     }
 }
@@ -0,0 +1,20 @@
+// Problems that do not stop a command, such as a mirror being tried or a cache write failing.
+// The library never prints them itself: the binary installs a handler that does, and other callers
+// see nothing unless they install one.
+
+use std::sync::OnceLock;
+
+type Handler = Box<dyn Fn(&str) + Send + Sync>;
+
+static HANDLER: OnceLock<Handler> = OnceLock::new();
+
+// a second call keeps the first handler
+pub fn set_handler(handler: impl Fn(&str) + Send + Sync + 'static) {
+    HANDLER.set(Box::new(handler)).ok();
+}
+
+pub fn warn(message: &str) {
+    if let Some(handler) = HANDLER.get() {
+        handler(message);
+    }
+}
@@ -0,0 +1,260 @@
+// downloads from a local server that fails in the ways upstreams and mirrors do
+#![cfg(unix)]
+
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use mine::config;
+use mine::downloader::{download_quietly, Checksum};
+use mine::error::MineError;
+
+const SIZE: usize = 64 * 1024;
+
+// the content of a file, different for every name so the cache cannot serve one for another
+fn body(name: &str) -> Vec<u8> {
+    let seed = name.bytes().map(usize::from).sum::<usize>();
+    (0..SIZE).map(|i| ((i + seed) * 7 % 251) as u8).collect()
+}
+
+fn sha256(data: &[u8]) -> Checksum {
+    Checksum::Sha256(format!("{:x}", Sha256::digest(data)))
+}
+
+fn md5(data: &[u8]) -> Checksum {
+    Checksum::Md5(format!("{:x}", Md5::digest(data)))
+}
+
+struct Server {
+    address: String,
+    // the path and Range header of every request
+    requests: Mutex<Vec<(String, Option<String>)>>,
+}
+
+// a server whose first directory says how it behaves:
+//   /ok/       serves the body, honouring Range
+//   /flaky/    sends half the body and closes the connection, then acts like /ok/
+//   /norange/  like /flaky/, then ignores Range
+//   /badrange/ like /flaky/, then answers a Range with the wrong part once, then acts like /ok/
+//   anything else is a 404
+fn server() -> &'static Server {
+    static SERVER: OnceLock<Server> = OnceLock::new();
+    SERVER.get_or_init(|| {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for socket in listener.incoming() {
+                std::thread::spawn(move || respond(socket.unwrap()));
+            }
+        });
+        Server { address, requests: Mutex::new(Vec::new()) }
+    })
+}
+
+fn respond(mut socket: TcpStream) {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.ends_with(b"\r\n\r\n") {
+        match socket.read(&mut buf).unwrap() {
+            0 => return,
+            n => request.extend_from_slice(&buf[..n]),
+        }
+    }
+    let request = String::from_utf8_lossy(&request).to_string();
+    let path = request.split(' ').nth(1).unwrap_or_default().to_string();
+    let range = request.lines()
+        .find_map(|line| line.to_lowercase().strip_prefix("range: bytes=").map(|range| range.trim_end_matches('-').to_string()));
+
+    let earlier = {
+        let mut requests = server().requests.lock().unwrap();
+        requests.push((path.clone(), range.clone()));
+        requests.iter().filter(|(requested, _)| *requested == path).count() - 1
+    };
+
+    let body = body(path.rsplit('/').next().unwrap_or_default());
+    let start: usize = range.as_deref().and_then(|start| start.parse().ok()).unwrap_or(0);
+    let behaviour = path.split('/').nth(1).unwrap_or_default();
+    let (status, headers, content): (&str, String, &[u8]) = match (behaviour, earlier, range.is_some()) {
+        ("flaky" | "norange" | "badrange", 0, _) => {
+            let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", SIZE);
+            socket.write_all(head.as_bytes()).unwrap();
+            socket.write_all(&body[..SIZE / 2]).unwrap();
+            return;
+        },
+        ("badrange", 1, true) => ("206 Partial Content", format!("Content-Range: bytes 0-{}/{}\r\n", SIZE - 1, SIZE), &body[..]),
+        ("ok" | "flaky" | "badrange", _, true) if start >= SIZE => ("416 Range Not Satisfiable", String::new(), &[]),
+        ("ok" | "flaky" | "badrange", _, true) => ("206 Partial Content", format!("Content-Range: bytes {}-{}/{}\r\n", start, SIZE - 1, SIZE), &body[start..]),
+        ("ok" | "flaky" | "norange" | "badrange", _, _) => ("200 OK", String::new(), &body[..]),
+        _ => ("404 Not Found", String::new(), &[]),
+    };
+    let head = format!("HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n", status, headers, content.len());
+    socket.write_all(head.as_bytes()).ok();
+    socket.write_all(content).ok();
+}
+
+// the Range of every request for a path, None for a request without one
+fn ranges(path: &str) -> Vec<Option<u64>> {
+    server().requests.lock().unwrap().iter()
+        .filter(|(requested, _)| requested == path)
+        .map(|(_, range)| range.as_ref().map(|start| start.parse().unwrap()))
+        .collect()
+}
+
+// a directory for the test's files, with the cache and config in a directory of their own
+fn dir(test: &str) -> PathBuf {
+    static HOME: OnceLock<PathBuf> = OnceLock::new();
+    let home = HOME.get_or_init(|| {
+        let home = env::temp_dir().join(format!("mine-downloader-test-{}", std::process::id()));
+        fs::remove_dir_all(&home).ok();
+        env::set_var("HOME", &home);
+        env::set_var("XDG_CACHE_HOME", home.join("cache"));
+        env::set_var("XDG_CONFIG_HOME", home.join("config"));
+        env::remove_var(config::CONFIG_ENV);
+        // Modrinth's CDN answers 404, its mirror is fine
+        env::set_var("MINE_UPSTREAM_MODRINTH_CDN_URL", format!("{}/missing", server().address));
+        env::set_var("MINE_UPSTREAM_MODRINTH_CDN_MIRRORS", format!("{}/ok", server().address));
+        config::init().unwrap();
+        home
+    });
+    let dir = home.join(test);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn url(path: &str) -> String {
+    format!("{}{}", server().address, path)
+}
+
+#[tokio::test]
+async fn retries_interrupted_downloads_with_range() {
+    let path = dir("retry").join("server.jar");
+    let started = Instant::now();
+    let downloaded = download_quietly(&url("/flaky/retry.jar"), &path, Some(&sha256(&body("retry.jar")))).await.unwrap();
+
+    assert_eq!(fs::read(&path).unwrap(), body("retry.jar"));
+    assert_eq!(downloaded.size, SIZE as u64);
+    assert_eq!(ranges("/flaky/retry.jar"), [None, Some(SIZE as u64 / 2)]);
+    // after the initial backoff
+    assert!(started.elapsed() >= Duration::from_millis(500));
+}
+
+#[tokio::test]
+async fn restarts_when_the_server_ignores_range() {
+    let path = dir("norange").join("server.jar");
+    let downloaded = download_quietly(&url("/norange/norange.jar"), &path, None).await.unwrap();
+
+    assert_eq!(fs::read(&path).unwrap(), body("norange.jar"));
+    assert_eq!(downloaded.size, SIZE as u64);
+    assert_eq!(ranges("/norange/norange.jar"), [None, Some(SIZE as u64 / 2)]);
+}
+
+#[tokio::test]
+async fn restarts_when_the_wrong_range_comes_back() {
+    let path = dir("badrange").join("server.jar");
+    download_quietly(&url("/badrange/badrange.jar"), &path, None).await.unwrap();
+
+    assert_eq!(fs::read(&path).unwrap(), body("badrange.jar"));
+    assert_eq!(ranges("/badrange/badrange.jar"), [None, Some(SIZE as u64 / 2), None]);
+}
+
+#[tokio::test]
+async fn checksum_mismatches_leave_the_target_untouched() {
+    let dir = dir("mismatch");
+    let path = dir.join("server.jar");
+    fs::write(&path, "the previous server").unwrap();
+
+    let error = download_quietly(&url("/ok/mismatch.jar"), &path, Some(&sha256(b"something else"))).await.err().unwrap();
+    assert!(matches!(error, MineError::Checksum(_)), "{}", error);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "the previous server");
+    assert!(!dir.join("server.jar.part").exists());
+}
+
+#[tokio::test]
+async fn failed_downloads_remove_their_part() {
+    let dir = dir("missing");
+    let error = download_quietly(&url("/missing/missing.jar"), &dir.join("server.jar"), None).await.err().unwrap();
+    assert!(matches!(error, MineError::HttpStatus { status: 404, .. }), "{}", error);
+    assert!(!dir.join("server.jar").exists());
+    assert!(!dir.join("server.jar.part").exists());
+}
+
+#[tokio::test]
+async fn continues_a_part_from_an_earlier_run() {
+    let dir = dir("resume");
+    fs::write(dir.join("server.jar.part"), &body("resume.jar")[..1000]).unwrap();
+    download_quietly(&url("/ok/resume.jar"), &dir.join("server.jar"), Some(&sha256(&body("resume.jar")))).await.unwrap();
+
+    assert_eq!(fs::read(dir.join("server.jar")).unwrap(), body("resume.jar"));
+    assert!(!dir.join("server.jar.part").exists());
+    assert_eq!(ranges("/ok/resume.jar"), [Some(1000)]);
+}
+
+#[tokio::test]
+async fn a_complete_part_is_only_checked() {
+    let dir = dir("complete");
+    fs::write(dir.join("server.jar.part"), body("complete.jar")).unwrap();
+    download_quietly(&url("/ok/complete.jar"), &dir.join("server.jar"), Some(&sha256(&body("complete.jar")))).await.unwrap();
+
+    assert_eq!(fs::read(dir.join("server.jar")).unwrap(), body("complete.jar"));
+    assert_eq!(ranges("/ok/complete.jar"), [Some(SIZE as u64)]);
+}
+
+#[tokio::test]
+async fn a_part_of_another_file_is_downloaded_again() {
+    let dir = dir("stale");
+    fs::write(dir.join("server.jar.part"), "an older server").unwrap();
+    download_quietly(&url("/ok/stale.jar"), &dir.join("server.jar"), Some(&sha256(&body("stale.jar")))).await.unwrap();
+
+    assert_eq!(fs::read(dir.join("server.jar")).unwrap(), body("stale.jar"));
+    assert_eq!(ranges("/ok/stale.jar"), [Some(15), None]);
+}
+
+#[tokio::test]
+async fn parts_are_only_continued_with_a_checksum() {
+    let dir = dir("unchecked");
+    fs::write(dir.join("server.jar.part"), "an older server").unwrap();
+    download_quietly(&url("/ok/unchecked.jar"), &dir.join("server.jar"), None).await.unwrap();
+
+    assert_eq!(fs::read(dir.join("server.jar")).unwrap(), body("unchecked.jar"));
+    assert_eq!(ranges("/ok/unchecked.jar"), [None]);
+}
+
+#[tokio::test]
+async fn falls_back_to_mirrors() {
+    let path = dir("mirror").join("plugin.jar");
+    download_quietly("https://cdn.modrinth.com/data/mirror.jar", &path, Some(&sha256(&body("mirror.jar")))).await.unwrap();
+
+    assert_eq!(fs::read(&path).unwrap(), body("mirror.jar"));
+    assert_eq!(ranges("/missing/data/mirror.jar"), [None]);
+    assert_eq!(ranges("/ok/data/mirror.jar"), [None]);
+}
+
+#[tokio::test]
+async fn downloads_are_cached() {
+    let dir = dir("cached");
+    let checksum = sha256(&body("cached.jar"));
+    download_quietly(&url("/ok/cached.jar"), &dir.join("first.jar"), Some(&checksum)).await.unwrap();
+    download_quietly(&url("/ok/cached.jar"), &dir.join("second.jar"), Some(&checksum)).await.unwrap();
+
+    assert_eq!(fs::read(dir.join("second.jar")).unwrap(), body("cached.jar"));
+    assert_eq!(ranges("/ok/cached.jar"), [None]);
+}
+
+#[tokio::test]
+async fn checks_and_caches_by_md5() {
+    let dir = dir("md5");
+    let error = download_quietly(&url("/ok/md5.jar"), &dir.join("wrong.jar"), Some(&md5(b"something else"))).await.err().unwrap();
+    assert!(matches!(error, MineError::Checksum(_)), "{}", error);
+    assert!(!dir.join("wrong.jar").exists());
+
+    download_quietly(&url("/ok/md5.jar"), &dir.join("first.jar"), Some(&md5(&body("md5.jar")))).await.unwrap();
+    download_quietly(&url("/ok/md5.jar"), &dir.join("second.jar"), Some(&md5(&body("md5.jar")))).await.unwrap();
+    assert_eq!(fs::read(dir.join("second.jar")).unwrap(), body("md5.jar"));
+    assert_eq!(ranges("/ok/md5.jar"), [None, None]);
+}
@@ -0,0 +1,84 @@
+// resolves providers against a local meta API, configured through MINE_UPSTREAM_*_URL and _MIRRORS
+#![cfg(unix)]
+
+use std::env;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use mine::config;
+use mine::manifest::Server;
+use mine::server::provider;
+
+const META: &str = r#"{
+    "game": [{"version": "1.21-rc1", "stable": false}, {"version": "1.21", "stable": true}, {"version": "1.20.6", "stable": true}],
+    "loader": [{"version": "0.16.0", "stable": true}],
+    "installer": [{"version": "1.0.1", "stable": true}]
+}"#;
+
+// serves META at /meta/v2/versions and 404s everything else, recording the requested paths
+async fn serve() -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let recorded = requests.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                match socket.read(&mut buf).await.unwrap() {
+                    0 => break,
+                    n => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            let request = String::from_utf8_lossy(&request);
+            let path = request.split(' ').nth(1).unwrap_or_default().to_string();
+            let response = match path.as_str() {
+                "/meta/v2/versions" => format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", META.len(), META),
+                _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            };
+            recorded.lock().unwrap().push(path);
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    (address, requests)
+}
+
+#[tokio::test]
+async fn resolves_through_configured_upstreams() {
+    let (address, requests) = serve().await;
+
+    // nothing from the user's cache or config, and nothing written to them
+    let home = env::temp_dir().join(format!("mine-upstream-test-{}", std::process::id()));
+    env::set_var("HOME", &home);
+    env::set_var("XDG_CACHE_HOME", home.join("cache"));
+    env::set_var("XDG_CONFIG_HOME", home.join("config"));
+    env::remove_var(config::CONFIG_ENV);
+
+    env::set_var("MINE_UPSTREAM_FABRIC_URL", format!("{}/meta", address));
+    // the URL fails, so the mirror is used
+    env::set_var("MINE_UPSTREAM_LEGACY_FABRIC_URL", format!("{}/missing", address));
+    env::set_var("MINE_UPSTREAM_LEGACY_FABRIC_MIRRORS", format!("{}/meta/", address));
+    config::init().unwrap();
+
+    let server = Server { kind: "fabric".to_string(), ..Server::default() };
+    let fabric = provider::provider("fabric").unwrap();
+    let resolved = fabric.resolve(&server).await.unwrap();
+    assert_eq!(resolved.version, "1.21");
+    assert_eq!(resolved.loader.as_deref(), Some("0.16.0"));
+    assert_eq!(resolved.installer.as_deref(), Some("1.0.1"));
+
+    // mine.lock keeps the upstream URL, whichever server answered
+    let artifacts = fabric.artifacts(&resolved).await.unwrap();
+    assert_eq!(artifacts[0].url, "https://meta.fabricmc.net/v2/versions/loader/1.21/0.16.0/1.0.1/server/jar");
+
+    let server = Server { kind: "legacy-fabric".to_string(), version: "1.20.6".to_string(), ..Server::default() };
+    let resolved = provider::provider("legacy-fabric").unwrap().resolve(&server).await.unwrap();
+    assert_eq!(resolved.version, "1.20.6");
+
+    assert_eq!(*requests.lock().unwrap(), ["/meta/v2/versions", "/missing/v2/versions", "/meta/v2/versions"]);
+    std::fs::remove_dir_all(&home).ok();
+}